@@ -1,4 +1,4 @@
-use async_graphql::SimpleObject;
+use async_graphql::{InputObject, SimpleObject};
 use serde::{Deserialize, Serialize};
 
 pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
@@ -12,6 +12,34 @@ pub struct ChessMove {
     pub san: String,
     pub piece: String,
     pub timestamp: u64,
+    /// Letter of a piece dropped from the pocket onto `to`, with `from` left empty
+    pub drop: Option<String>,
+}
+
+/// Text notation accepted by `PlaceMoveNotation`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum, Copy)]
+pub enum MoveNotation {
+    /// Standard Algebraic Notation, e.g. `Nxe5+`
+    San,
+    /// UCI long algebraic, e.g. `e7e8q`
+    Uci,
+}
+
+/// Rule set a game is played under
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum, Copy, Default)]
+pub enum Variant {
+    #[default]
+    Standard,
+    /// Bringing the king to d4, e4, d5 or e5 wins
+    KingOfTheHill,
+    /// Giving the third check wins
+    ThreeCheck,
+    /// Captures are compulsory and losing every piece wins
+    Antichess,
+    /// Captured pieces change sides and can be dropped back on the board
+    Crazyhouse,
+    /// Crazyhouse on two linked boards: captures feed the partner's pocket
+    Bughouse,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum, Copy)]
@@ -22,6 +50,24 @@ pub enum GameResult {
     InProgress,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum, Copy)]
+pub enum Termination {
+    Checkmate,
+    Stalemate,
+    InsufficientMaterial,
+    ThreefoldRepetition,
+    FivefoldRepetition,
+    FiftyMoveRule,
+    SeventyFiveMoveRule,
+    Resignation,
+    Agreement,
+    KingInCenter,
+    ThirdCheck,
+    AllPiecesLost,
+    Timeout,
+    TimeoutVsInsufficientMaterial,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum, Copy)]
 pub enum BetSelection {
     White,
@@ -43,6 +89,154 @@ pub enum GameStatus {
     Waiting,
     Active,
     Finished,
+    /// Invite declined, withdrawn or expired before the game started
+    Cancelled,
+    /// Called off before both players had moved
+    Aborted,
+}
+
+/// Why an operation was rejected
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum GameError {
+    GameNotFound(String),
+    NotAPlayer,
+    NotInvited,
+    GameNotPending,
+    GameNotActive,
+    NotYourTurn,
+    IllegalMove(String),
+    InvalidStartingPosition(String),
+    InvalidTimeControl(String),
+    Chess960RequiresStandard,
+    DrawOfferPending,
+    NoDrawOffer,
+    OwnDrawOffer,
+    NoClaimableDraw,
+    OwnClock,
+    OpponentHasTime,
+    NotBughouse,
+    InvalidGameChain(String),
+    InviteExpired,
+    CannotCancel,
+}
+
+impl std::fmt::Display for GameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameError::GameNotFound(game_id) => write!(f, "Game {} not found on this chain", game_id),
+            GameError::NotAPlayer => write!(f, "Not a player in this game"),
+            GameError::NotInvited => write!(f, "Not invited to this game"),
+            GameError::GameNotPending => write!(f, "Game is not pending"),
+            GameError::GameNotActive => write!(f, "Game is not active"),
+            GameError::NotYourTurn => write!(f, "Not your turn"),
+            GameError::IllegalMove(e) => write!(f, "Illegal move: {}", e),
+            GameError::InvalidStartingPosition(e) => write!(f, "Invalid starting position: {}", e),
+            GameError::InvalidTimeControl(e) => write!(f, "Invalid time control: {}", e),
+            GameError::Chess960RequiresStandard => write!(f, "Chess960 requires standard rules"),
+            GameError::DrawOfferPending => write!(f, "A draw offer is already pending"),
+            GameError::NoDrawOffer => write!(f, "No draw offer pending"),
+            GameError::OwnDrawOffer => write!(f, "Cannot answer your own draw offer"),
+            GameError::NoClaimableDraw => write!(f, "No draw can be claimed in this position"),
+            GameError::OwnClock => write!(f, "Cannot claim a timeout on your own move"),
+            GameError::OpponentHasTime => write!(f, "Opponent still has time left"),
+            GameError::NotBughouse => write!(f, "Not a Bughouse game"),
+            GameError::InvalidGameChain(e) => write!(f, "Invalid game chain: {}", e),
+            GameError::InviteExpired => write!(f, "Invite has expired"),
+            GameError::CannotCancel => write!(f, "Game can no longer be cancelled"),
+        }
+    }
+}
+
+/// How each move's time is credited back to the mover
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum, Copy, Default)]
+pub enum ClockMode {
+    /// Fischer: the bonus is added after every move
+    #[default]
+    Increment,
+    /// US delay: the clock waits the bonus before it starts counting down
+    SimpleDelay,
+    /// Bronstein: time used on the move is returned, up to the bonus
+    BronsteinDelay,
+}
+
+/// One period of a time control
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, SimpleObject, InputObject)]
+#[graphql(input_name = "TimeStageInput")]
+pub struct TimeStage {
+    /// Moves to make in this period, 0 for the rest of the game
+    pub moves: u32,
+    /// Seconds added to the clock when the period starts
+    pub seconds: u32,
+    /// Increment or delay per move in seconds
+    pub bonus: u32,
+}
+
+/// Clock rules of a game, untimed when there are no stages
+///
+/// A final stage with a move count repeats, so `40/7200` adds two hours
+/// every 40 moves.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default, SimpleObject, InputObject)]
+#[graphql(input_name = "TimeControlInput")]
+pub struct TimeControl {
+    pub mode: ClockMode,
+    pub stages: Vec<TimeStage>,
+}
+
+impl TimeControl {
+    /// Base time plus a Fischer increment, both in seconds
+    pub fn fischer(seconds: u32, increment: u32) -> Self {
+        TimeControl {
+            mode: ClockMode::Increment,
+            stages: vec![TimeStage { moves: 0, seconds, bonus: increment }],
+        }
+    }
+}
+
+/// Named time controls selectable in `CreateGame`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, async_graphql::Enum, Copy)]
+pub enum TimeControlPreset {
+    /// 1+0
+    Bullet,
+    /// 3+2
+    Blitz,
+    /// 10+5
+    Rapid,
+    /// 90 minutes for 40 moves, then 30 minutes, with 30 seconds per move throughout
+    Classical,
+}
+
+impl TimeControlPreset {
+    pub fn time_control(self) -> TimeControl {
+        match self {
+            TimeControlPreset::Bullet => TimeControl::fischer(60, 0),
+            TimeControlPreset::Blitz => TimeControl::fischer(180, 2),
+            TimeControlPreset::Rapid => TimeControl::fischer(600, 5),
+            TimeControlPreset::Classical => TimeControl {
+                mode: ClockMode::Increment,
+                stages: vec![
+                    TimeStage { moves: 40, seconds: 5400, bonus: 30 },
+                    TimeStage { moves: 0, seconds: 1800, bonus: 30 },
+                ],
+            },
+        }
+    }
+}
+
+/// A draw offer waiting for the opponent's answer
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, SimpleObject)]
+pub struct DrawOffer {
+    /// Chain of the player who offered
+    pub offered_by: String,
+    /// Moves played when the offer was made
+    pub ply: u32,
+}
+
+/// The other board of a Bughouse match
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, SimpleObject)]
+pub struct PartnerBoard {
+    pub game_id: String,
+    pub white_player: String,
+    pub black_player: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
@@ -52,10 +246,31 @@ pub struct ChessGame {
     pub black_player: String,
     pub fen: String,
     pub current_fen: String,
+    /// Zobrist key of `current_fen` as 16 hex digits
+    pub zobrist_key: String,
+    /// Set when the opponent's chain reported a different position
+    pub desynced: bool,
     pub moves: Vec<ChessMove>,
     pub move_count: u32,
     pub status: GameStatus,
     pub result: Option<GameResult>,
+    pub termination: Option<Termination>,
+    /// Offer expires when the offerer's opponent moves instead of answering
+    pub draw_offer: Option<DrawOffer>,
+    pub time_control: TimeControl,
+    /// White's remaining time in microseconds when its clock last stopped
+    pub white_time_left: u64,
+    /// Black's remaining time in microseconds when its clock last stopped
+    pub black_time_left: u64,
+    /// When the side to move's clock started running, 0 before the game starts
+    pub clock_started_at: u64,
+    /// Fischer Random: the back rank is drawn when the game is accepted
+    pub chess960: bool,
+    pub variant: Variant,
+    /// Bughouse only: the board our captures are passed to
+    pub bughouse_partner: Option<PartnerBoard>,
+    /// Chain owning the canonical copy of a hosted game; player chains only mirror it
+    pub game_chain: Option<String>,
     pub created_at: u64,
     pub updated_at: u64,
 }
@@ -66,6 +281,8 @@ pub enum BetStatus {
     Won,
     Lost,
     Cancelled,
+    /// Stake of a cancelled bet paid back to the bettor
+    Refunded,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
@@ -10,6 +10,6 @@ pub mod chess;
 
 // Re-export chess types for easy access
 pub use chess::{
-    ChessMove, GameResult, BetSelection, GameOdds, GameStatus,
-    ChessGame, BetStatus, BetRecord, BetPool, STARTING_FEN
+    ChessMove, MoveNotation, Variant, GameResult, Termination, BetSelection, GameOdds, GameStatus, GameError,
+    ChessGame, DrawOffer, PartnerBoard, ClockMode, TimeStage, TimeControl, TimeControlPreset, BetStatus, BetRecord, BetPool, STARTING_FEN
 };
@@ -88,11 +88,17 @@ impl Contract for BettingContract {
             }
 
             Operation::ClaimWinnings { bet_id } => {
-                let bet = self.state.get_bet(&bet_id).await
+                let mut bet = self.state.get_bet(&bet_id).await
                     .expect("Failed to get bet")
                     .expect("Bet not found");
 
                 assert!(bet.bettor == self.runtime.chain_id().to_string(), "Not your bet");
+
+                // Bets on a cancelled game get their stake back, once
+                if let Some(stake) = claim_refund(&mut bet) {
+                    self.state.update_bet_status(&bet_id, bet.status).await.expect("Failed to mark bet refunded");
+                    return OperationResult::WinningsClaimed { bet_id, winnings: stake };
+                }
                 assert!(bet.status == BetStatus::Won, "Bet is not in Won status");
 
                 let _result = self.state.settled_games.get(&bet.game_id).await
@@ -128,6 +134,16 @@ impl Contract for BettingContract {
                 self.state.minimum_bet.set(amount);
                 OperationResult::ConfigUpdated
             }
+
+            Operation::RefundGame { game_id } => {
+                let caller = self.runtime.authenticated_caller_id();
+                assert!(
+                    caller.is_some() && caller == *self.state.game_app.get(),
+                    "Only the game application can refund a game"
+                );
+                self.refund_game(&game_id).await.expect("Failed to refund game");
+                OperationResult::GameRefunded { game_id }
+            }
         }
     }
 
@@ -153,6 +169,16 @@ impl Contract for BettingContract {
 
 impl BettingContract {
     async fn settle_game(&mut self, game_id: &str, result: abi::GameResult) -> Result<(), String> {
+        // The first result stands; a late report from the other player's chain can't settle the game again
+        let settled = self
+            .state
+            .settled_games
+            .contains_key(game_id)
+            .await
+            .map_err(|e| format!("Failed to check result: {}", e))?;
+        if settled {
+            return Ok(());
+        }
         self.state.settled_games.insert(game_id, result).map_err(|e| format!("Failed to store result: {}", e))?;
         let bets = self.state.get_game_bets(game_id).await?;
         let winning_selection = match result {
@@ -167,4 +193,34 @@ impl BettingContract {
         }
         Ok(())
     }
+
+    /// Close betting on a game that was called off and mark its open bets for refund
+    async fn refund_game(&mut self, game_id: &str) -> Result<(), String> {
+        self.state.lock_game(game_id).await?;
+        let bets = self.state.get_game_bets(game_id).await?;
+        for mut bet in bets {
+            if cancel_bet(&mut bet) {
+                self.state.update_bet_status(&bet.id, bet.status).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Cancel a bet that is still open, returning whether it was
+fn cancel_bet(bet: &mut BetRecord) -> bool {
+    if bet.status != BetStatus::Pending {
+        return false;
+    }
+    bet.status = BetStatus::Cancelled;
+    true
+}
+
+/// Stake to pay back for a cancelled bet, marking it refunded so it is paid only once
+fn claim_refund(bet: &mut BetRecord) -> Option<u64> {
+    if bet.status != BetStatus::Cancelled {
+        return None;
+    }
+    bet.status = BetStatus::Refunded;
+    Some(bet.amount)
 }
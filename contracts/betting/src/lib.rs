@@ -23,6 +23,8 @@ pub enum Operation {
     SetTokenApp { token_app: ApplicationId },
     SetGameApp { game_app: ApplicationId },
     SetMinimumBet { amount: u64 },
+    /// Return the stakes on a game that was called off; only the game application may call this
+    RefundGame { game_id: String },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +40,7 @@ pub enum OperationResult {
     BetPlaced { bet_id: String, amount: u64, odds: u64 },
     WinningsClaimed { bet_id: String, winnings: u64 },
     OddsUpdated { game_id: String },
+    GameRefunded { game_id: String },
     ConfigUpdated,
 }
 
@@ -132,6 +132,35 @@ fn test_game_finished_message() {
     assert!(true, "Test placeholder - game finished message");
 }
 
+/// Test refunding the bets on a cancelled game
+#[test]
+fn test_cancelled_game_refund() {
+    let bet = |id: &str, status| BetRecord {
+        id: id.to_string(),
+        game_id: "game_1".to_string(),
+        bettor: "bettor_chain".to_string(),
+        amount: 250,
+        bet_on: BetSelection::White,
+        odds: 2000,
+        status,
+        timestamp: 0,
+    };
+
+    // Only open bets are cancelled
+    let mut open = bet("bet_1", BetStatus::Pending);
+    let mut settled = bet("bet_2", BetStatus::Lost);
+    assert!(cancel_bet(&mut open));
+    assert!(!cancel_bet(&mut settled));
+    assert_eq!(open.status, BetStatus::Cancelled);
+    assert_eq!(settled.status, BetStatus::Lost);
+
+    // The stake comes back once
+    assert_eq!(claim_refund(&mut open), Some(250));
+    assert_eq!(open.status, BetStatus::Refunded);
+    assert_eq!(claim_refund(&mut open), None);
+    assert_eq!(claim_refund(&mut settled), None);
+}
+
 /// Integration test: Complete betting flow
 #[test]
 fn test_complete_betting_flow() {
@@ -0,0 +1,153 @@
+//! Bitboard primitives and precomputed attack tables
+//!
+//! A bitboard is a `u64` with one bit per square. Square indices are
+//! `rank * 8 + file` with rank 0 being the eighth rank, matching the
+//! `Position.board` array layout so both views address squares identically.
+//! All tables are built at compile time, so nothing is computed at runtime
+//! inside contract execution.
+
+pub type Bitboard = u64;
+
+/// Square index for board coordinates
+pub const fn square(rank: usize, file: usize) -> usize {
+    rank * 8 + file
+}
+
+/// Board coordinates `(rank, file)` for a square index
+pub const fn coords(square: usize) -> (usize, usize) {
+    (square / 8, square % 8)
+}
+
+/// Bitboard with a single square set
+pub const fn bit(square: usize) -> Bitboard {
+    1 << square
+}
+
+/// Iterate over the set squares of a bitboard, lowest index first
+pub fn squares(mut bitboard: Bitboard) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        if bitboard == 0 {
+            return None;
+        }
+        let square = bitboard.trailing_zeros() as usize;
+        bitboard &= bitboard - 1;
+        Some(square)
+    })
+}
+
+/// Squares whose rank and file indices sum to an even number
+pub const EVEN_SQUARES: Bitboard = {
+    let mut bitboard = 0;
+    let mut sq = 0;
+    while sq < 64 {
+        if (sq / 8 + sq % 8) % 2 == 0 {
+            bitboard |= bit(sq);
+        }
+        sq += 1;
+    }
+    bitboard
+};
+
+/// The first and eighth ranks, where no pawn may stand
+pub const BACK_RANKS: Bitboard = 0xff00_0000_0000_00ff;
+
+/// The four centre squares d4, e4, d5 and e5
+pub const CENTER: Bitboard = bit(square(3, 3)) | bit(square(3, 4)) | bit(square(4, 3)) | bit(square(4, 4));
+
+/// Squares attacked by a knight standing on each square
+pub const KNIGHT_ATTACKS: [Bitboard; 64] = step_table(&[
+    (-2, -1), (-2, 1), (-1, -2), (-1, 2),
+    (1, -2), (1, 2), (2, -1), (2, 1),
+]);
+
+/// Squares attacked by a king standing on each square
+pub const KING_ATTACKS: [Bitboard; 64] = step_table(&[
+    (-1, -1), (-1, 0), (-1, 1), (0, -1),
+    (0, 1), (1, -1), (1, 0), (1, 1),
+]);
+
+/// Squares attacked by a pawn on each square, indexed by color (white first)
+pub const PAWN_ATTACKS: [[Bitboard; 64]; 2] = [
+    step_table(&[(-1, -1), (-1, 1)]),
+    step_table(&[(1, -1), (1, 1)]),
+];
+
+/// Ray directions as `(rank_step, file_step)`; the first four increase the square index
+const DIRECTIONS: [(i32, i32); 8] = [
+    (1, 0), (0, 1), (1, 1), (1, -1),
+    (-1, 0), (0, -1), (-1, -1), (-1, 1),
+];
+
+const ROOK_DIRECTIONS: [usize; 4] = [0, 1, 4, 5];
+const BISHOP_DIRECTIONS: [usize; 4] = [2, 3, 6, 7];
+
+/// Squares from each square to the board edge in each direction, excluding the origin
+const RAYS: [[Bitboard; 64]; 8] = {
+    let mut rays = [[0; 64]; 8];
+    let mut dir = 0;
+    while dir < 8 {
+        let (rank_step, file_step) = DIRECTIONS[dir];
+        let mut sq = 0;
+        while sq < 64 {
+            let mut rank = (sq / 8) as i32 + rank_step;
+            let mut file = (sq % 8) as i32 + file_step;
+            while rank >= 0 && rank < 8 && file >= 0 && file < 8 {
+                rays[dir][sq] |= bit(square(rank as usize, file as usize));
+                rank += rank_step;
+                file += file_step;
+            }
+            sq += 1;
+        }
+        dir += 1;
+    }
+    rays
+};
+
+/// Build a lookup of single-step attacks for the given offsets
+const fn step_table(offsets: &[(i32, i32)]) -> [Bitboard; 64] {
+    let mut table = [0; 64];
+    let mut sq = 0;
+    while sq < 64 {
+        let mut i = 0;
+        while i < offsets.len() {
+            let rank = (sq / 8) as i32 + offsets[i].0;
+            let file = (sq % 8) as i32 + offsets[i].1;
+            if rank >= 0 && rank < 8 && file >= 0 && file < 8 {
+                table[sq] |= bit(square(rank as usize, file as usize));
+            }
+            i += 1;
+        }
+        sq += 1;
+    }
+    table
+}
+
+/// Squares reached along one ray, stopping at (and including) the first blocker
+fn ray_attacks(dir: usize, square: usize, occupied: Bitboard) -> Bitboard {
+    let ray = RAYS[dir][square];
+    let blockers = ray & occupied;
+    if blockers == 0 {
+        return ray;
+    }
+
+    let first = if dir < 4 {
+        blockers.trailing_zeros() as usize
+    } else {
+        63 - blockers.leading_zeros() as usize
+    };
+    ray ^ RAYS[dir][first]
+}
+
+/// Squares attacked by a bishop on `square` given the board occupancy
+pub fn bishop_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    BISHOP_DIRECTIONS
+        .iter()
+        .fold(0, |attacks, &dir| attacks | ray_attacks(dir, square, occupied))
+}
+
+/// Squares attacked by a rook on `square` given the board occupancy
+pub fn rook_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    ROOK_DIRECTIONS
+        .iter()
+        .fold(0, |attacks, &dir| attacks | ray_attacks(dir, square, occupied))
+}
@@ -2,6 +2,10 @@
 //!
 //! This module provides core chess functionality without external dependencies.
 //! It implements FEN parsing, move application, and game end detection.
+//! Move generation and attack detection run on the bitboards in `bitboard`.
+
+use crate::bitboard::{self, Bitboard};
+use abi::{GameResult, Termination, Variant, STARTING_FEN};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Piece {
@@ -19,6 +23,48 @@ pub enum Color {
     Black,
 }
 
+/// Why a FEN string was rejected
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum FenError {
+    #[error("Invalid FEN: expected 4 to 6 fields, found {0}")]
+    FieldCount(usize),
+    #[error("Invalid FEN: board must have 8 ranks, found {0}")]
+    RankCount(usize),
+    #[error("Invalid FEN: rank {0} does not have 8 squares")]
+    RankLength(usize),
+    #[error("Invalid piece: {0}")]
+    Piece(char),
+    #[error("Invalid active color: {0}")]
+    ActiveColor(String),
+    #[error("Invalid castling rights: {0}")]
+    Castling(String),
+    #[error("Invalid en passant square: {0}")]
+    EnPassant(String),
+    #[error("Invalid halfmove clock: {0}")]
+    HalfmoveClock(String),
+    #[error("Invalid fullmove number: {0}")]
+    FullmoveNumber(String),
+    #[error("Invalid remaining checks: {0}")]
+    RemainingChecks(String),
+    #[error("Invalid pocket: {0}")]
+    Pocket(String),
+    #[error("Illegal position: {color:?} has {count} kings")]
+    KingCount { color: Color, count: u32 },
+    #[error("Illegal position: pawn on back rank at {0}")]
+    PawnOnBackRank(String),
+    #[error("Illegal position: the side not to move is in check")]
+    OpponentInCheck,
+    #[error("Illegal position: castling right {0} without king and rook on their home squares")]
+    CastlingRight(char),
+    #[error("Illegal position: no pawn could have just passed {0}")]
+    EnPassantSquare(String),
+}
+
+/// A chess position
+///
+/// Pieces are kept both in the `board` array (fast "what is on this square")
+/// and in per-piece bitboards (fast attack and move generation). The two
+/// views are only ever written together through `set_square`.
 #[derive(Debug, Clone)]
 pub struct Position {
     pub board: [[Option<(Piece, Color)>; 8]; 8],
@@ -27,6 +73,21 @@ pub struct Position {
     pub en_passant: Option<(usize, usize)>,
     pub halfmove_clock: u32,
     pub fullmove_number: u32,
+    zobrist_key: u64,
+    /// Bitboard per color and piece kind, indexed `[color][piece]`
+    pieces: [[Bitboard; 6]; 2],
+    /// Occupied squares per color
+    occupied: [Bitboard; 2],
+    /// Chess960 castling: the king moves onto its own rook to castle
+    chess960: bool,
+    /// Rule set deciding move legality and how the game ends
+    variant: Variant,
+    /// Checks given by each side, counted for Three-check
+    checks_given: [u8; 2],
+    /// Captured pieces each side may drop, indexed by color then pawn..queen
+    pockets: [[u8; 5]; 2],
+    /// Pieces that were promoted and return to a pocket as pawns
+    promoted: Bitboard,
 }
 
 #[derive(Debug, Clone)]
@@ -35,68 +96,434 @@ pub struct CastlingRights {
     pub white_queenside: bool,
     pub black_kingside: bool,
     pub black_queenside: bool,
+    /// Home file of each castling rook, indexed `[color][kingside, queenside]`
+    pub rook_files: [[usize; 2]; 2],
+}
+
+impl CastlingRights {
+    fn none() -> Self {
+        CastlingRights {
+            white_kingside: false,
+            white_queenside: false,
+            black_kingside: false,
+            black_queenside: false,
+            rook_files: [[7, 0], [7, 0]],
+        }
+    }
+
+    /// Whether `color` may still castle on the given side
+    pub fn has(&self, color: Color, kingside: bool) -> bool {
+        match (color, kingside) {
+            (Color::White, true) => self.white_kingside,
+            (Color::White, false) => self.white_queenside,
+            (Color::Black, true) => self.black_kingside,
+            (Color::Black, false) => self.black_queenside,
+        }
+    }
+
+    fn set(&mut self, color: Color, kingside: bool, allowed: bool) {
+        match (color, kingside) {
+            (Color::White, true) => self.white_kingside = allowed,
+            (Color::White, false) => self.white_queenside = allowed,
+            (Color::Black, true) => self.black_kingside = allowed,
+            (Color::Black, false) => self.black_queenside = allowed,
+        }
+    }
+
+    /// File of the rook that castles with `color` on the given side
+    pub fn rook_file(&self, color: Color, kingside: bool) -> usize {
+        self.rook_files[color as usize][!kingside as usize]
+    }
+}
+
+/// How a game ended on the board
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameEnd {
+    Checkmate { winner: Color },
+    Stalemate,
+    InsufficientMaterial,
+    FivefoldRepetition,
+    SeventyFiveMoveRule,
+    /// Claimable only, see [`Position::claimable_draw`]
+    ThreefoldRepetition,
+    /// Claimable only, see [`Position::claimable_draw`]
+    FiftyMoveRule,
+    /// King of the Hill: a king reached d4, e4, d5 or e5
+    KingInCenter { winner: Color },
+    /// Three-check: a side gave its third check
+    ThirdCheck { winner: Color },
+    /// Antichess: the side to move has lost all its pieces and wins
+    AllPiecesLost { winner: Color },
+    /// Antichess: the side to move has no legal move and wins
+    NoMovesLeft { winner: Color },
+}
+
+impl GameEnd {
+    /// Final result for this ending
+    pub fn result(self) -> GameResult {
+        match self {
+            GameEnd::Checkmate { winner }
+            | GameEnd::KingInCenter { winner }
+            | GameEnd::ThirdCheck { winner }
+            | GameEnd::AllPiecesLost { winner }
+            | GameEnd::NoMovesLeft { winner } => match winner {
+                Color::White => GameResult::WhiteWins,
+                Color::Black => GameResult::BlackWins,
+            },
+            _ => GameResult::Draw,
+        }
+    }
+
+    /// Termination reason recorded on the game
+    pub fn termination(self) -> Termination {
+        match self {
+            GameEnd::Checkmate { .. } => Termination::Checkmate,
+            GameEnd::Stalemate => Termination::Stalemate,
+            GameEnd::InsufficientMaterial => Termination::InsufficientMaterial,
+            GameEnd::FivefoldRepetition => Termination::FivefoldRepetition,
+            GameEnd::SeventyFiveMoveRule => Termination::SeventyFiveMoveRule,
+            GameEnd::ThreefoldRepetition => Termination::ThreefoldRepetition,
+            GameEnd::FiftyMoveRule => Termination::FiftyMoveRule,
+            GameEnd::KingInCenter { .. } => Termination::KingInCenter,
+            GameEnd::ThirdCheck { .. } => Termination::ThirdCheck,
+            GameEnd::AllPiecesLost { .. } => Termination::AllPiecesLost,
+            GameEnd::NoMovesLeft { .. } => Termination::Stalemate,
+        }
+    }
+}
+
+/// A move in board coordinates, as produced by the move generator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub from: (usize, usize),
+    pub to: (usize, usize),
+    pub promotion: Option<Piece>,
+    /// Piece taken from the pocket; `from` then equals `to`
+    pub drop: Option<Piece>,
+}
+
+impl Move {
+    fn new(from: (usize, usize), to: (usize, usize)) -> Self {
+        Move { from, to, promotion: None, drop: None }
+    }
+
+    /// Drop of a pocket piece onto `to`
+    fn new_drop(piece: Piece, to: (usize, usize)) -> Self {
+        Move { from: to, to, promotion: None, drop: Some(piece) }
+    }
+
+    /// Origin square name, e.g. `e2`, empty for drops
+    pub fn from_square(self) -> String {
+        if self.drop.is_some() {
+            return String::new();
+        }
+        Position::square_name(self.from.0, self.from.1)
+    }
+
+    /// Destination square name, e.g. `e4`
+    pub fn to_square(self) -> String {
+        Position::square_name(self.to.0, self.to.1)
+    }
+
+    /// Lowercase promotion letter as accepted by `PlaceMove`
+    pub fn promotion_letter(self) -> Option<String> {
+        self.promotion
+            .map(|piece| Position::piece_to_char(piece, Color::Black).to_string())
+    }
+
+    /// Uppercase letter of a dropped piece as accepted by `PlaceDrop`
+    pub fn drop_letter(self) -> Option<String> {
+        self.drop
+            .map(|piece| Position::piece_to_char(piece, Color::White).to_string())
+    }
+
+    /// Long algebraic (UCI) form, e.g. `e2e4`, `e7e8q` or `N@f3`
+    pub fn to_uci(self) -> String {
+        if let Some(letter) = self.drop_letter() {
+            return format!("{}@{}", letter, self.to_square());
+        }
+        let mut uci = self.from_square();
+        uci.push_str(&self.to_square());
+        uci.push_str(&self.promotion_letter().unwrap_or_default());
+        uci
+    }
+}
+
+impl Color {
+    /// The other side
+    pub fn opposite(self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+
+    /// Board rank index of this side's back rank
+    fn home_rank(self) -> usize {
+        match self {
+            Color::White => 7,
+            Color::Black => 0,
+        }
+    }
+
+    /// Rank step of a pawn push (rank 0 is the eighth rank)
+    fn pawn_direction(self) -> i32 {
+        match self {
+            Color::White => -1,
+            Color::Black => 1,
+        }
+    }
+}
+
+const PROMOTION_PIECES: [Piece; 4] = [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight];
+
+/// Pieces that can sit in a pocket, in the order pockets are written in FEN
+const POCKET_PIECES: [Piece; 5] = [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight, Piece::Pawn];
+
+/// Zobrist keys: 12 piece kinds x 64 squares, side to move, 4 castling rights, 8 en passant files,
+/// then 10 pocket piece kinds x 16 counts
+const ZOBRIST_KEYS: [u64; 941] = zobrist_keys();
+const ZOBRIST_BLACK_TO_MOVE: usize = 768;
+const ZOBRIST_CASTLING: usize = 769;
+const ZOBRIST_EN_PASSANT: usize = 773;
+const ZOBRIST_POCKET: usize = 781;
+const ZOBRIST_POCKET_DEPTH: usize = 16;
+
+/// Fill the Zobrist table from a fixed-seed splitmix64 stream
+///
+/// The seed is part of the on-chain format: both players' chains must derive
+/// identical keys, so it must never change.
+const fn zobrist_keys() -> [u64; 941] {
+    let mut keys = [0u64; 941];
+    let mut state: u64 = 0x5eed_c4e5_5c4e_55ed;
+    let mut i = 0;
+    while i < keys.len() {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+}
+
+/// Zobrist key of a piece standing on a square
+fn zobrist_piece(piece: Piece, color: Color, rank: usize, file: usize) -> u64 {
+    let kind = piece as usize * 2 + color as usize;
+    ZOBRIST_KEYS[kind * 64 + rank * 8 + file]
 }
 
 impl Position {
     /// Parse FEN string into Position
-    pub fn from_fen(fen: &str) -> Result<Self, String> {
-        let parts: Vec<&str> = fen.split_whitespace().collect();
-        if parts.len() < 4 {
-            return Err("Invalid FEN: not enough parts".to_string());
+    ///
+    /// The string must be well formed and describe a legal position, see
+    /// [`Position::validate`].
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        Self::from_fen_variant(fen, Variant::Standard)
+    }
+
+    /// Parse a FEN string for a game played under `variant`
+    ///
+    /// Three-check positions may carry the remaining checks of each side as
+    /// an extra field after the en passant square, e.g. `3+2`. Crazyhouse
+    /// and Bughouse pockets follow the board in brackets, e.g.
+    /// `.../RNBQKBNR[Qn]`, and promoted pieces are marked with `~`.
+    pub fn from_fen_variant(fen: &str, variant: Variant) -> Result<Self, FenError> {
+        let mut parts: Vec<&str> = fen.split_whitespace().collect();
+        let checks_given = match parts.get(4) {
+            Some(field) if field.contains('+') => {
+                let checks = Self::parse_remaining_checks(field)?;
+                parts.remove(4);
+                checks
+            }
+            _ => [0, 0],
+        };
+        if !(4..=6).contains(&parts.len()) {
+            return Err(FenError::FieldCount(parts.len()));
         }
 
-        let board = Self::parse_board(parts[0])?;
+        let (placement, pockets) = match parts[0].split_once('[') {
+            Some((placement, pocket)) => {
+                let pocket = pocket.strip_suffix(']').ok_or_else(|| FenError::Pocket(pocket.to_string()))?;
+                (placement, Self::parse_pockets(pocket)?)
+            }
+            None => (parts[0], [[0; 5]; 2]),
+        };
+        let (board, promoted) = Self::parse_board(placement)?;
         let active_color = match parts[1] {
             "w" => Color::White,
             "b" => Color::Black,
-            _ => return Err("Invalid active color".to_string()),
+            other => return Err(FenError::ActiveColor(other.to_string())),
         };
 
-        let castling = Self::parse_castling(parts[2])?;
+        let (castling, chess960) = Self::parse_castling(parts[2], &board)?;
         let en_passant = Self::parse_en_passant(parts[3])?;
 
-        let halfmove_clock = if parts.len() > 4 {
-            parts[4].parse().unwrap_or(0)
-        } else {
-            0
+        let halfmove_clock = match parts.get(4) {
+            Some(field) => field.parse().map_err(|_| FenError::HalfmoveClock(field.to_string()))?,
+            None => 0,
         };
 
-        let fullmove_number = if parts.len() > 5 {
-            parts[5].parse().unwrap_or(1)
-        } else {
-            1
+        let fullmove_number = match parts.get(5) {
+            Some(field) => field
+                .parse()
+                .ok()
+                .filter(|&number| number >= 1)
+                .ok_or_else(|| FenError::FullmoveNumber(field.to_string()))?,
+            None => 1,
         };
 
-        Ok(Position {
+        let mut position = Position {
             board,
             active_color,
             castling,
             en_passant,
             halfmove_clock,
             fullmove_number,
-        })
+            zobrist_key: 0,
+            pieces: [[0; 6]; 2],
+            occupied: [0; 2],
+            chess960,
+            variant,
+            checks_given,
+            pockets,
+            promoted,
+        };
+        for (rank, row) in board.iter().enumerate() {
+            for (file, square) in row.iter().enumerate() {
+                if let Some((piece, color)) = *square {
+                    let bit = bitboard::bit(bitboard::square(rank, file));
+                    position.pieces[color as usize][piece as usize] |= bit;
+                    position.occupied[color as usize] |= bit;
+                }
+            }
+        }
+        position.validate()?;
+        position.zobrist_key = position.compute_board_key();
+
+        Ok(position)
     }
 
-    /// Parse board part of FEN
+    /// Check the position could arise in a legal game
+    ///
+    /// Each side needs exactly one king, no pawn may stand on the first or
+    /// eighth rank, the side that just moved cannot be left in check, every
+    /// castling right needs its king and rook at home, and an en passant
+    /// square needs the pawn that just passed it. Antichess drops the king
+    /// rules and does not allow castling, and only drop variants may have
+    /// pieces in hand.
+    pub fn validate(&self) -> Result<(), FenError> {
+        // Antichess kings are ordinary pieces: any number, never in check
+        for color in [Color::White, Color::Black] {
+            let count = self.pieces[color as usize][Piece::King as usize].count_ones();
+            if count != 1 && self.variant != Variant::Antichess {
+                return Err(FenError::KingCount { color, count });
+            }
+        }
+
+        let pawns = self.pieces[0][Piece::Pawn as usize] | self.pieces[1][Piece::Pawn as usize];
+        if let Some(square) = bitboard::squares(pawns & bitboard::BACK_RANKS).next() {
+            let (rank, file) = bitboard::coords(square);
+            return Err(FenError::PawnOnBackRank(Self::square_name(rank, file)));
+        }
+
+        if self.is_in_check(self.active_color.opposite()) {
+            return Err(FenError::OpponentInCheck);
+        }
+
+        if !self.has_pockets() && self.pockets != [[0; 5]; 2] {
+            return Err(FenError::Pocket(self.pocket_field()));
+        }
+
+        let rights = [
+            (Color::White, true, 'K'),
+            (Color::White, false, 'Q'),
+            (Color::Black, true, 'k'),
+            (Color::Black, false, 'q'),
+        ];
+        for (color, kingside, symbol) in rights {
+            if !self.castling.has(color, kingside) {
+                continue;
+            }
+            if self.variant == Variant::Antichess {
+                return Err(FenError::CastlingRight(symbol));
+            }
+            // The rook must stand on the castling side of a king still on its home rank
+            let rank = color.home_rank();
+            let rook_file = self.castling.rook_file(color, kingside);
+            let king_home = self
+                .king_square(color)
+                .filter(|&(king_rank, _)| king_rank == rank)
+                .is_some_and(|(_, king_file)| (rook_file > king_file) == kingside);
+            if !king_home || self.board[rank][rook_file] != Some((Piece::Rook, color)) {
+                return Err(FenError::CastlingRight(symbol));
+            }
+        }
+
+        if let Some((rank, file)) = self.en_passant {
+            // The pawn that just moved stands one rank past the square, its start square is empty
+            let mover = self.active_color.opposite();
+            let expected_rank = (mover.home_rank() as i32 + 2 * mover.pawn_direction()) as usize;
+            let landing = (rank as i32 + mover.pawn_direction()) as usize;
+            let start = (rank as i32 - mover.pawn_direction()) as usize;
+            if rank != expected_rank
+                || self.board[landing][file] != Some((Piece::Pawn, mover))
+                || self.board[rank][file].is_some()
+                || self.board[start][file].is_some()
+            {
+                return Err(FenError::EnPassantSquare(Self::square_name(rank, file)));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Initial position of a variant, in the form [`Position::to_fen`] writes it
+    pub fn starting_fen(variant: Variant) -> String {
+        match variant {
+            Variant::Standard | Variant::KingOfTheHill => STARTING_FEN.to_string(),
+            Variant::ThreeCheck => STARTING_FEN.replacen(" - ", " - 3+3 ", 1),
+            Variant::Antichess => STARTING_FEN.replacen("KQkq", "-", 1),
+            Variant::Crazyhouse | Variant::Bughouse => STARTING_FEN.replacen(" w ", "[] w ", 1),
+        }
+    }
+
+    /// Parse the Three-check `white+black` remaining checks field into checks given
+    fn parse_remaining_checks(field: &str) -> Result<[u8; 2], FenError> {
+        let invalid = || FenError::RemainingChecks(field.to_string());
+        let (white, black) = field.split_once('+').ok_or_else(invalid)?;
+        let given = |remaining: &str| match remaining.parse::<u8>() {
+            Ok(remaining) if remaining <= 3 => Ok(3 - remaining),
+            _ => Err(invalid()),
+        };
+        Ok([given(white)?, given(black)?])
+    }
+
+    /// Parse board part of FEN, returning the promoted pieces marked with `~`
     #[allow(clippy::type_complexity)]
-    fn parse_board(board_str: &str) -> Result<[[Option<(Piece, Color)>; 8]; 8], String> {
+    fn parse_board(board_str: &str) -> Result<([[Option<(Piece, Color)>; 8]; 8], Bitboard), FenError> {
         let mut board = [[None; 8]; 8];
+        let mut promoted = 0;
         let ranks: Vec<&str> = board_str.split('/').collect();
 
         if ranks.len() != 8 {
-            return Err("Invalid FEN: board must have 8 ranks".to_string());
+            return Err(FenError::RankCount(ranks.len()));
         }
 
         for (rank_idx, rank_str) in ranks.iter().enumerate() {
+            let rank_number = 8 - rank_idx;
             let mut file_idx = 0;
             for ch in rank_str.chars() {
-                if file_idx >= 8 {
-                    return Err("Invalid FEN: rank too long".to_string());
+                if file_idx >= 8 && ch != '~' {
+                    return Err(FenError::RankLength(rank_number));
                 }
 
-                if ch.is_ascii_digit() {
+                if ('1'..='8').contains(&ch) {
                     let empty_squares = ch.to_digit(10).unwrap() as usize;
                     file_idx += empty_squares;
+                } else if ch == '~' && file_idx > 0 && board[rank_idx][file_idx - 1].is_some() {
+                    promoted |= bitboard::bit(bitboard::square(rank_idx, file_idx - 1));
                 } else {
                     let (piece, color) = Self::parse_piece(ch)?;
                     board[rank_idx][file_idx] = Some((piece, color));
@@ -105,15 +532,27 @@ impl Position {
             }
 
             if file_idx != 8 {
-                return Err("Invalid FEN: rank too short".to_string());
+                return Err(FenError::RankLength(rank_number));
             }
         }
 
-        Ok(board)
+        Ok((board, promoted))
+    }
+
+    /// Parse the pieces in hand between the brackets, uppercase for white
+    fn parse_pockets(pocket: &str) -> Result<[[u8; 5]; 2], FenError> {
+        let mut pockets = [[0; 5]; 2];
+        for ch in pocket.chars() {
+            match Self::parse_piece(ch) {
+                Ok((piece, color)) if piece != Piece::King => pockets[color as usize][piece as usize] += 1,
+                _ => return Err(FenError::Pocket(pocket.to_string())),
+            }
+        }
+        Ok(pockets)
     }
 
     /// Parse piece character
-    fn parse_piece(ch: char) -> Result<(Piece, Color), String> {
+    fn parse_piece(ch: char) -> Result<(Piece, Color), FenError> {
         let color = if ch.is_uppercase() {
             Color::White
         } else {
@@ -127,45 +566,67 @@ impl Position {
             'r' => Piece::Rook,
             'q' => Piece::Queen,
             'k' => Piece::King,
-            _ => return Err(format!("Invalid piece: {}", ch)),
+            _ => return Err(FenError::Piece(ch)),
         };
 
         Ok((piece, color))
     }
 
-    /// Parse castling rights
-    fn parse_castling(castling_str: &str) -> Result<CastlingRights, String> {
+    /// Parse castling rights in standard, X-FEN or Shredder-FEN notation
+    ///
+    /// `KQkq` name the outermost rook on that side of the king, a file letter
+    /// (`A`-`H` for white, `a`-`h` for black) names the rook directly. Also
+    /// reports whether the rights need Chess960 castling rules.
+    fn parse_castling(
+        castling_str: &str,
+        board: &[[Option<(Piece, Color)>; 8]; 8],
+    ) -> Result<(CastlingRights, bool), FenError> {
+        let mut rights = CastlingRights::none();
+        let mut chess960 = false;
         if castling_str == "-" {
-            return Ok(CastlingRights {
-                white_kingside: false,
-                white_queenside: false,
-                black_kingside: false,
-                black_queenside: false,
-            });
+            return Ok((rights, chess960));
         }
 
-        Ok(CastlingRights {
-            white_kingside: castling_str.contains('K'),
-            white_queenside: castling_str.contains('Q'),
-            black_kingside: castling_str.contains('k'),
-            black_queenside: castling_str.contains('q'),
-        })
+        let invalid = || FenError::Castling(castling_str.to_string());
+        for c in castling_str.chars() {
+            let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+            let home = &board[color.home_rank()];
+            let king_file = (0..8).find(|&f| home[f] == Some((Piece::King, color)));
+            let mut rooks = (0..8).filter(|&f| home[f] == Some((Piece::Rook, color)));
+
+            let (kingside, rook_file) = match c.to_ascii_lowercase() {
+                'k' => (true, rooks.filter(|&f| king_file.is_some_and(|k| f > k)).max().unwrap_or(7)),
+                'q' => (false, rooks.find(|&f| king_file.is_some_and(|k| f < k)).unwrap_or(0)),
+                letter @ 'a'..='h' => {
+                    let file = (letter as u8 - b'a') as usize;
+                    chess960 = true;
+                    (file > king_file.unwrap_or(4), file)
+                }
+                _ => return Err(invalid()),
+            };
+
+            if rights.has(color, kingside) {
+                return Err(invalid());
+            }
+            rights.set(color, kingside, true);
+            rights.rook_files[color as usize][!kingside as usize] = rook_file;
+            if king_file != Some(4) || rook_file != if kingside { 7 } else { 0 } {
+                chess960 = true;
+            }
+        }
+
+        Ok((rights, chess960))
     }
 
     /// Parse en passant square
-    fn parse_en_passant(ep_str: &str) -> Result<Option<(usize, usize)>, String> {
+    fn parse_en_passant(ep_str: &str) -> Result<Option<(usize, usize)>, FenError> {
         if ep_str == "-" {
             return Ok(None);
         }
 
-        if ep_str.len() != 2 {
-            return Err("Invalid en passant square".to_string());
-        }
-
-        let file = (ep_str.chars().nth(0).unwrap() as u8 - b'a') as usize;
-        let rank = 8 - (ep_str.chars().nth(1).unwrap().to_digit(10).unwrap() as usize);
-
-        Ok(Some((rank, file)))
+        Self::parse_square(ep_str)
+            .map(Some)
+            .map_err(|_| FenError::EnPassant(ep_str.to_string()))
     }
 
     /// Convert position to FEN string
@@ -194,6 +655,26 @@ impl Position {
         }
         fen.pop(); // Remove trailing '/'
 
+        // Promoted pieces and pockets, drop variants only
+        if self.has_pockets() {
+            let mut board = String::new();
+            let mut square = 0;
+            for ch in fen.chars() {
+                board.push(ch);
+                match ch {
+                    '1'..='8' => square += ch.to_digit(10).unwrap() as usize,
+                    '/' => {}
+                    _ => {
+                        if self.promoted & bitboard::bit(square) != 0 {
+                            board.push('~');
+                        }
+                        square += 1;
+                    }
+                }
+            }
+            fen = format!("{}[{}]", board, self.pocket_field());
+        }
+
         // Active color
         fen.push(' ');
         fen.push(match self.active_color {
@@ -203,19 +684,7 @@ impl Position {
 
         // Castling
         fen.push(' ');
-        let mut castling = String::new();
-        if self.castling.white_kingside {
-            castling.push('K');
-        }
-        if self.castling.white_queenside {
-            castling.push('Q');
-        }
-        if self.castling.black_kingside {
-            castling.push('k');
-        }
-        if self.castling.black_queenside {
-            castling.push('q');
-        }
+        let castling = self.castling_field();
         fen.push_str(if castling.is_empty() { "-" } else { &castling });
 
         // En passant
@@ -228,6 +697,12 @@ impl Position {
             }
         }
 
+        // Remaining checks, Three-check only
+        if self.variant == Variant::ThreeCheck {
+            let [white, black] = self.checks_given.map(|given| 3 - given.min(3));
+            fen.push_str(&format!(" {}+{}", white, black));
+        }
+
         // Halfmove and fullmove
         fen.push(' ');
         fen.push_str(&self.halfmove_clock.to_string());
@@ -237,6 +712,119 @@ impl Position {
         fen
     }
 
+    /// Castling field in X-FEN: `KQkq` unless another rook stands further out
+    ///
+    /// Standard positions always use `KQkq`; a Chess960 right whose rook is
+    /// not the outermost one on its side is written as the rook's file letter.
+    fn castling_field(&self) -> String {
+        let mut field = String::new();
+        for color in [Color::White, Color::Black] {
+            let home = &self.board[color.home_rank()];
+            for kingside in [true, false] {
+                if !self.castling.has(color, kingside) {
+                    continue;
+                }
+                let rook_file = self.castling.rook_file(color, kingside);
+                let outer_files = if kingside { rook_file + 1..8 } else { 0..rook_file };
+                let outermost = outer_files.clone().all(|f| home[f] != Some((Piece::Rook, color)));
+                let symbol = if !self.chess960 || outermost {
+                    if kingside { 'k' } else { 'q' }
+                } else {
+                    (b'a' + rook_file as u8) as char
+                };
+                field.push(match color {
+                    Color::White => symbol.to_ascii_uppercase(),
+                    Color::Black => symbol,
+                });
+            }
+        }
+        field
+    }
+
+    /// Rule set this position is played under
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// Checks given so far by `color`, counted in Three-check
+    pub fn checks_given(&self, color: Color) -> u8 {
+        self.checks_given[color as usize]
+    }
+
+    /// Whether captured pieces go to a pocket and can be dropped back
+    fn has_pockets(&self) -> bool {
+        matches!(self.variant, Variant::Crazyhouse | Variant::Bughouse)
+    }
+
+    /// Number of `piece` in the pocket of `color`
+    pub fn pocket_count(&self, color: Color, piece: Piece) -> u8 {
+        match piece {
+            Piece::King => 0,
+            _ => self.pockets[color as usize][piece as usize],
+        }
+    }
+
+    /// Put a piece in the pocket of `color`, e.g. one passed over by a Bughouse partner
+    pub fn add_to_pocket(&mut self, color: Color, piece: Piece) -> Result<(), String> {
+        if !self.has_pockets() || piece == Piece::King {
+            return Err("No pocket for this piece".to_string());
+        }
+        self.pockets[color as usize][piece as usize] += 1;
+        Ok(())
+    }
+
+    /// Parse a pocket piece letter, uppercase for white (`P`, `N`, `B`, `R`, `Q`)
+    pub fn parse_pocket_letter(letter: &str) -> Result<(Piece, Color), String> {
+        match letter.chars().collect::<Vec<_>>().as_slice() {
+            [ch] => match Self::parse_piece(*ch) {
+                Ok((piece, color)) if piece != Piece::King => Ok((piece, color)),
+                _ => Err(format!("Invalid pocket piece: {}", letter)),
+            },
+            _ => Err(format!("Invalid pocket piece: {}", letter)),
+        }
+    }
+
+    /// Letter of a pocket piece as written in FEN, uppercase for white
+    pub fn pocket_letter(piece: Piece, color: Color) -> String {
+        Self::piece_to_char(piece, color).to_string()
+    }
+
+    /// Piece a move would put in hand: the captured piece, or a pawn if it
+    /// had been promoted
+    pub fn pocket_capture(&self, mv: &Move) -> Option<Piece> {
+        if mv.drop.is_some() || self.castling_side(mv).is_some() {
+            return None;
+        }
+        match self.board[mv.to.0][mv.to.1] {
+            Some(_) if self.promoted & bitboard::bit(bitboard::square(mv.to.0, mv.to.1)) != 0 => Some(Piece::Pawn),
+            Some((piece, _)) => Some(piece),
+            None if self.is_capture(mv) => Some(Piece::Pawn),
+            None => None,
+        }
+    }
+
+    /// Pockets in FEN bracket order, white pieces first
+    fn pocket_field(&self) -> String {
+        let mut field = String::new();
+        for color in [Color::White, Color::Black] {
+            for piece in POCKET_PIECES {
+                let letter = Self::piece_to_char(piece, color);
+                field.extend(std::iter::repeat_n(letter, self.pockets[color as usize][piece as usize] as usize));
+            }
+        }
+        field
+    }
+
+    /// Whether castling follows Chess960 rules and encoding
+    pub fn is_chess960(&self) -> bool {
+        self.chess960
+    }
+
+    /// Switch to Chess960 castling, needed for 960 games whose start looks standard
+    pub fn enable_chess960(&mut self) {
+        self.chess960 = true;
+    }
+
     fn piece_to_char(piece: Piece, color: Color) -> char {
         let ch = match piece {
             Piece::Pawn => 'p',
@@ -262,113 +850,553 @@ impl Position {
     }
 
     /// Parse algebraic square to indices
-    fn parse_square(square: &str) -> Result<(usize, usize), String> {
+    pub fn parse_square(square: &str) -> Result<(usize, usize), String> {
         if square.len() != 2 {
             return Err("Invalid square".to_string());
         }
 
-        let file = (square.chars().nth(0).unwrap() as u8 - b'a') as usize;
-        let rank = 8 - (square.chars().nth(1).unwrap().to_digit(10).ok_or("Invalid rank")? as usize);
-
-        if file >= 8 || rank >= 8 {
+        let bytes = square.as_bytes();
+        if !(b'a'..=b'h').contains(&bytes[0]) || !(b'1'..=b'8').contains(&bytes[1]) {
             return Err("Square out of bounds".to_string());
         }
 
+        let file = (bytes[0] - b'a') as usize;
+        let rank = (b'8' - bytes[1]) as usize;
+
         Ok((rank, file))
     }
 
-    /// Validate if a move is legal according to chess rules
-    fn is_legal_move(&self, from_rank: usize, from_file: usize, to_rank: usize, to_file: usize, piece: Piece, color: Color) -> bool {
-        // Can't move to same square
-        if from_rank == to_rank && from_file == to_file {
-            return false;
+    /// Convert board indices back to an algebraic square
+    pub fn square_name(rank: usize, file: usize) -> String {
+        format!("{}{}", (b'a' + file as u8) as char, 8 - rank)
+    }
+
+    /// Generate every legal move for the side to move
+    ///
+    /// Pseudo-legal moves are generated per piece and then filtered by playing
+    /// each one on a copy of the board and rejecting those that leave the
+    /// mover's king attacked. This covers pins, discovered checks and moving
+    /// the king into check.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        if self.variant == Variant::Antichess {
+            // No king safety, but capturing is compulsory
+            let moves = self.pseudo_legal_moves();
+            let captures: Vec<Move> = moves.iter().copied().filter(|mv| self.is_capture(mv)).collect();
+            return if captures.is_empty() { moves } else { captures };
         }
 
-        // Can't capture own piece
-        if let Some((_, target_color)) = self.board[to_rank][to_file] {
-            if target_color == color {
-                return false;
+        let color = self.active_color;
+        self.pseudo_legal_moves()
+            .into_iter()
+            .filter(|mv| {
+                let mut next = self.clone();
+                next.make_move(mv);
+                !next.is_in_check(color)
+            })
+            .collect()
+    }
+
+    /// Generate moves that follow piece movement rules, ignoring king safety
+    fn pseudo_legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::with_capacity(48);
+        let us = self.active_color;
+        let them = us.opposite();
+
+        // The enemy king is never capturable: a position where it could be
+        // taken is already illegal. Antichess kings are captured like any piece.
+        let targets = !self.occupied[us as usize] & !self.protected_king(them);
+
+        for piece in [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen, Piece::King] {
+            for from in bitboard::squares(self.pieces[us as usize][piece as usize]) {
+                let attacks = self.attacks_from(piece, us, from) & targets;
+                for to in bitboard::squares(attacks) {
+                    moves.push(Move::new(bitboard::coords(from), bitboard::coords(to)));
+                }
             }
         }
 
-        let rank_diff = (to_rank as i32 - from_rank as i32).abs();
-        let file_diff = (to_file as i32 - from_file as i32).abs();
+        self.pawn_moves(us, &mut moves);
+        self.castling_moves(us, &mut moves);
+        self.drop_moves(us, &mut moves);
+
+        moves
+    }
 
+    /// Drops of pocket pieces onto empty squares, pawns not on the back ranks
+    fn drop_moves(&self, color: Color, moves: &mut Vec<Move>) {
+        let empty = !self.all_occupied();
+        for piece in POCKET_PIECES {
+            if self.pockets[color as usize][piece as usize] == 0 {
+                continue;
+            }
+            let squares = if piece == Piece::Pawn { empty & !bitboard::BACK_RANKS } else { empty };
+            for to in bitboard::squares(squares) {
+                moves.push(Move::new_drop(piece, bitboard::coords(to)));
+            }
+        }
+    }
+
+    /// The king of `color` unless the variant lets kings be captured
+    fn protected_king(&self, color: Color) -> Bitboard {
+        match self.variant {
+            Variant::Antichess => 0,
+            _ => self.pieces[color as usize][Piece::King as usize],
+        }
+    }
+
+    /// Whether a move takes a piece, including en passant
+    fn is_capture(&self, mv: &Move) -> bool {
+        let target = self.board[mv.to.0][mv.to.1];
+        let pawn = self.board[mv.from.0][mv.from.1].map(|(p, _)| p) == Some(Piece::Pawn);
+        (target.is_some() && self.castling_side(mv).is_none()) || (pawn && mv.from.1 != mv.to.1)
+    }
+
+    /// Squares attacked by a piece standing on `square`
+    fn attacks_from(&self, piece: Piece, color: Color, square: usize) -> Bitboard {
+        let all = self.all_occupied();
         match piece {
-            Piece::Pawn => {
-                let direction = if color == Color::White { -1 } else { 1 };
-                let expected_rank = (from_rank as i32 + direction) as usize;
-                let start_rank = if color == Color::White { 6 } else { 1 };
-
-                // Forward move
-                if from_file == to_file && self.board[to_rank][to_file].is_none() {
-                    if to_rank == expected_rank {
-                        return true; // One square forward
-                    }
-                    // Two squares from start
-                    if from_rank == start_rank && to_rank == (from_rank as i32 + 2 * direction) as usize {
-                        let middle_rank = (from_rank as i32 + direction) as usize;
-                        return self.board[middle_rank][from_file].is_none();
-                    }
-                }
+            Piece::Pawn => bitboard::PAWN_ATTACKS[color as usize][square],
+            Piece::Knight => bitboard::KNIGHT_ATTACKS[square],
+            Piece::Bishop => bitboard::bishop_attacks(square, all),
+            Piece::Rook => bitboard::rook_attacks(square, all),
+            Piece::Queen => bitboard::bishop_attacks(square, all) | bitboard::rook_attacks(square, all),
+            Piece::King => bitboard::KING_ATTACKS[square],
+        }
+    }
 
-                // Diagonal capture
-                if file_diff == 1 && to_rank == expected_rank {
-                    return self.board[to_rank][to_file].is_some();
-                }
+    /// All occupied squares
+    fn all_occupied(&self) -> Bitboard {
+        self.occupied[0] | self.occupied[1]
+    }
+
+    /// King-side and queen-side castling
+    ///
+    /// Standard castling is encoded as a two-square king move, Chess960
+    /// castling as the king moving onto its own rook. Either way the king ends
+    /// on the g- or c-file and the rook next to it on the f- or d-file.
+    /// Requires the matching right, every square the king and rook cross or
+    /// land on to be empty apart from those two pieces, and that the king is
+    /// not in check and does not pass through or land on an attacked square.
+    fn castling_moves(&self, color: Color, moves: &mut Vec<Move>) {
+        if self.variant == Variant::Antichess {
+            return;
+        }
+        let home_rank = color.home_rank();
+        let Some((king_rank, king_file)) = self.king_square(color) else {
+            return;
+        };
+        if king_rank != home_rank || !(self.castling.has(color, true) || self.castling.has(color, false)) {
+            return;
+        }
 
-                false
+        let enemy = color.opposite();
+        if self.is_square_attacked(home_rank, king_file, enemy) {
+            return;
+        }
+
+        let rook = Some((Piece::Rook, color));
+        let all = self.all_occupied();
+        let span = |a: usize, b: usize| a.min(b)..=a.max(b);
+
+        for kingside in [true, false] {
+            let rook_file = self.castling.rook_file(color, kingside);
+            if !self.castling.has(color, kingside) || self.board[home_rank][rook_file] != rook {
+                continue;
             }
-            Piece::Knight => {
-                (rank_diff == 2 && file_diff == 1) || (rank_diff == 1 && file_diff == 2)
+
+            let (king_to, rook_to) = if kingside { (6, 5) } else { (2, 3) };
+            let empty = span(king_file, king_to)
+                .chain(span(rook_file, rook_to))
+                .filter(|&f| f != king_file && f != rook_file)
+                .all(|f| all & bitboard::bit(bitboard::square(home_rank, f)) == 0);
+            let safe = span(king_file, king_to).all(|f| !self.is_square_attacked(home_rank, f, enemy));
+
+            if empty && safe {
+                let to = if self.chess960 { rook_file } else { king_to };
+                moves.push(Move::new((home_rank, king_file), (home_rank, to)));
             }
-            Piece::Bishop => {
-                if rank_diff != file_diff {
-                    return false;
-                }
-                self.is_path_clear(from_rank, from_file, to_rank, to_file)
+        }
+    }
+
+    /// Castling side of a move, `Some(true)` for king-side
+    fn castling_side(&self, mv: &Move) -> Option<bool> {
+        let (piece, color) = self.board[mv.from.0][mv.from.1]?;
+        if piece != Piece::King {
+            return None;
+        }
+        if self.chess960 {
+            (self.board[mv.to.0][mv.to.1] == Some((Piece::Rook, color))).then_some(mv.to.1 > mv.from.1)
+        } else {
+            (mv.from.1.abs_diff(mv.to.1) == 2).then_some(mv.to.1 > mv.from.1)
+        }
+    }
+
+    /// Pawn pushes and diagonal captures
+    fn pawn_moves(&self, color: Color, moves: &mut Vec<Move>) {
+        let them = color.opposite();
+        let empty = !self.all_occupied();
+        let start_rank = if color == Color::White { 6 } else { 1 };
+
+        let mut capturable = self.occupied[them as usize] & !self.protected_king(them);
+        if let Some((ep_rank, ep_file)) = self.en_passant {
+            // Only a real double push leaves an en passant target behind
+            let pushed = (ep_rank as i32 - color.pawn_direction()) as usize;
+            if pushed < 8 && self.board[pushed][ep_file] == Some((Piece::Pawn, them)) {
+                capturable |= bitboard::bit(bitboard::square(ep_rank, ep_file));
             }
-            Piece::Rook => {
-                if from_rank != to_rank && from_file != to_file {
-                    return false;
+        }
+
+        for from in bitboard::squares(self.pieces[color as usize][Piece::Pawn as usize]) {
+            let (rank, file) = bitboard::coords(from);
+
+            // Forward pushes
+            let one_ahead = (rank as i32 + color.pawn_direction()) as usize;
+            if one_ahead < 8 && empty & bitboard::bit(bitboard::square(one_ahead, file)) != 0 {
+                self.push_pawn_move((rank, file), (one_ahead, file), moves);
+
+                let two_ahead = (rank as i32 + 2 * color.pawn_direction()) as usize;
+                if rank == start_rank && empty & bitboard::bit(bitboard::square(two_ahead, file)) != 0 {
+                    moves.push(Move::new((rank, file), (two_ahead, file)));
                 }
-                self.is_path_clear(from_rank, from_file, to_rank, to_file)
             }
-            Piece::Queen => {
-                let is_diagonal = rank_diff == file_diff;
-                let is_straight = from_rank == to_rank || from_file == to_file;
-                if !is_diagonal && !is_straight {
-                    return false;
+
+            // Diagonal captures, including en passant onto the skipped square
+            let captures = bitboard::PAWN_ATTACKS[color as usize][from] & capturable;
+            for to in bitboard::squares(captures) {
+                self.push_pawn_move((rank, file), bitboard::coords(to), moves);
+            }
+        }
+    }
+
+    /// Add a pawn move, expanding it into every promotion on the last rank
+    fn push_pawn_move(&self, from: (usize, usize), to: (usize, usize), moves: &mut Vec<Move>) {
+        if to.0 == 0 || to.0 == 7 {
+            for piece in PROMOTION_PIECES {
+                moves.push(Move { from, to, promotion: Some(piece), drop: None });
+            }
+            // Antichess pawns may also become kings
+            if self.variant == Variant::Antichess {
+                moves.push(Move { from, to, promotion: Some(Piece::King), drop: None });
+            }
+        } else {
+            moves.push(Move::new(from, to));
+        }
+    }
+
+    /// Check whether `square` is attacked by any piece of color `by`
+    pub fn is_square_attacked(&self, rank: usize, file: usize, by: Color) -> bool {
+        let square = bitboard::square(rank, file);
+        let theirs = &self.pieces[by as usize];
+        let all = self.all_occupied();
+
+        // A pawn of `by` attacks this square exactly when a pawn of the other
+        // color standing here would attack the pawn's square
+        bitboard::PAWN_ATTACKS[by.opposite() as usize][square] & theirs[Piece::Pawn as usize] != 0
+            || bitboard::KNIGHT_ATTACKS[square] & theirs[Piece::Knight as usize] != 0
+            || bitboard::KING_ATTACKS[square] & theirs[Piece::King as usize] != 0
+            || bitboard::bishop_attacks(square, all)
+                & (theirs[Piece::Bishop as usize] | theirs[Piece::Queen as usize])
+                != 0
+            || bitboard::rook_attacks(square, all)
+                & (theirs[Piece::Rook as usize] | theirs[Piece::Queen as usize])
+                != 0
+    }
+
+    /// Locate the king of the given color
+    pub fn king_square(&self, color: Color) -> Option<(usize, usize)> {
+        bitboard::squares(self.pieces[color as usize][Piece::King as usize])
+            .next()
+            .map(bitboard::coords)
+    }
+
+    /// Whether the king of the given color is currently attacked
+    pub fn is_in_check(&self, color: Color) -> bool {
+        if self.variant == Variant::Antichess {
+            return false;
+        }
+        match self.king_square(color) {
+            Some((rank, file)) => self.is_square_attacked(rank, file, color.opposite()),
+            None => false,
+        }
+    }
+
+    /// Count leaf nodes of the legal move tree to the given depth
+    ///
+    /// Comparing against published node counts is the standard way to catch
+    /// move generator bugs (castling, en passant, promotions, pins).
+    pub fn perft(&self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let moves = self.legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+
+        moves
+            .iter()
+            .map(|mv| {
+                let mut next = self.clone();
+                next.make_move(mv);
+                next.perft(depth - 1)
+            })
+            .sum()
+    }
+
+    /// Perft split by root move, as `(uci, nodes)` pairs sorted by move
+    ///
+    /// Diffing this against a reference engine's `divide` output pinpoints
+    /// the move whose subtree is miscounted.
+    pub fn divide(&self, depth: u32) -> Vec<(String, u64)> {
+        let mut counts: Vec<(String, u64)> = self
+            .legal_moves()
+            .iter()
+            .map(|mv| {
+                let mut next = self.clone();
+                next.make_move(mv);
+                (mv.to_uci(), next.perft(depth.saturating_sub(1)))
+            })
+            .collect();
+        counts.sort();
+        counts
+    }
+
+    /// Play a move on the board without validating it
+    ///
+    /// Callers must only pass moves produced by the move generator.
+    pub fn make_move(&mut self, mv: &Move) {
+        if let Some(piece) = mv.drop {
+            self.make_drop(piece, mv.to);
+            return;
+        }
+
+        let (from_rank, from_file) = mv.from;
+        let (to_rank, to_file) = mv.to;
+        let Some(piece_data) = self.board[from_rank][from_file] else {
+            return;
+        };
+
+        // Crazyhouse captures change sides; Bughouse captures go to the partner board
+        if self.variant == Variant::Crazyhouse {
+            if let Some(captured) = self.pocket_capture(mv) {
+                self.pockets[piece_data.1 as usize][captured as usize] += 1;
+            }
+        }
+        let from_bit = bitboard::bit(bitboard::square(from_rank, from_file));
+        let to_bit = bitboard::bit(bitboard::square(to_rank, to_file));
+        let was_promoted = self.promoted & from_bit != 0;
+        self.promoted &= !(from_bit | to_bit);
+        if was_promoted || mv.promotion.is_some() {
+            self.promoted |= to_bit;
+        }
+
+        // Castling rights and en passant are re-keyed once the move is done
+        self.zobrist_key ^= self.zobrist_state_key();
+
+        // Check if destination has a piece (capture) BEFORE moving; a Chess960
+        // castle lands on the king's own rook
+        let castle = self.castling_side(mv);
+        let is_capture = castle.is_none() && self.board[to_rank][to_file].is_some();
+
+        if let Some(kingside) = castle {
+            // Castling: lift king and rook, then drop them on their castled files
+            let rook_from = self.castling.rook_file(piece_data.1, kingside);
+            let rook = self.board[from_rank][rook_from];
+            let (king_to, rook_to) = if kingside { (6, 5) } else { (2, 3) };
+            self.set_square(from_rank, from_file, None);
+            self.set_square(from_rank, rook_from, None);
+            self.set_square(from_rank, king_to, Some(piece_data));
+            self.set_square(from_rank, rook_to, rook);
+        } else {
+            // En passant: a pawn moving diagonally onto an empty square takes the
+            // pawn that just passed it
+            if piece_data.0 == Piece::Pawn && from_file != to_file && !is_capture {
+                self.set_square(from_rank, to_file, None);
+            }
+
+            // Move piece, promoting if requested
+            let placed = match mv.promotion {
+                Some(promoted_piece) => (promoted_piece, piece_data.1),
+                None => piece_data,
+            };
+            self.set_square(to_rank, to_file, Some(placed));
+            self.set_square(from_rank, from_file, None);
+        }
+
+        // Update castling rights: a king move loses both, a rook leaving or
+        // being captured on its castling square loses that side's right
+        for color in [Color::White, Color::Black] {
+            for kingside in [true, false] {
+                let rook_square = (color.home_rank(), self.castling.rook_file(color, kingside));
+                let king_moved = piece_data == (Piece::King, color);
+                if king_moved || mv.from == rook_square || mv.to == rook_square {
+                    self.castling.set(color, kingside, false);
                 }
-                self.is_path_clear(from_rank, from_file, to_rank, to_file)
             }
-            Piece::King => {
-                rank_diff <= 1 && file_diff <= 1
+        }
+
+        // Update en passant (simplified - only for pawn double moves)
+        self.en_passant = None;
+        if piece_data.0 == Piece::Pawn {
+            let rank_diff = (to_rank as i32 - from_rank as i32).abs();
+            if rank_diff == 2 {
+                let ep_rank = (from_rank + to_rank) / 2;
+                self.en_passant = Some((ep_rank, from_file));
             }
         }
+
+        // Update clocks - check if pawn move or capture
+        if piece_data.0 == Piece::Pawn || is_capture {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+
+        // Switch active color
+        if self.active_color == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.active_color = self.active_color.opposite();
+
+        if self.variant == Variant::ThreeCheck && self.is_in_check(self.active_color) {
+            self.checks_given[piece_data.1 as usize] += 1;
+        }
+
+        self.zobrist_key ^= ZOBRIST_KEYS[ZOBRIST_BLACK_TO_MOVE] ^ self.zobrist_state_key();
     }
 
-    /// Check if path between two squares is clear (no pieces blocking)
-    fn is_path_clear(&self, from_rank: usize, from_file: usize, to_rank: usize, to_file: usize) -> bool {
-        let rank_step = (to_rank as i32 - from_rank as i32).signum();
-        let file_step = (to_file as i32 - from_file as i32).signum();
+    /// Drop a pocket piece of the side to move onto an empty square
+    fn make_drop(&mut self, piece: Piece, (rank, file): (usize, usize)) {
+        let color = self.active_color;
+        self.zobrist_key ^= self.zobrist_state_key();
 
-        let mut rank = from_rank as i32 + rank_step;
-        let mut file = from_file as i32 + file_step;
+        self.pockets[color as usize][piece as usize] -= 1;
+        self.set_square(rank, file, Some((piece, color)));
+        self.en_passant = None;
+        // A drop empties the pocket for good, so like a pawn move it is irreversible
+        self.halfmove_clock = 0;
+
+        if color == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.active_color = color.opposite();
 
-        while rank != to_rank as i32 || file != to_file as i32 {
-            if self.board[rank as usize][file as usize].is_some() {
-                return false;
+        self.zobrist_key ^= ZOBRIST_KEYS[ZOBRIST_BLACK_TO_MOVE] ^ self.zobrist_state_key();
+    }
+
+    /// Write a square, keeping the bitboards and Zobrist key in step with the board
+    fn set_square(&mut self, rank: usize, file: usize, square: Option<(Piece, Color)>) {
+        let bit = bitboard::bit(bitboard::square(rank, file));
+        if let Some((piece, color)) = self.board[rank][file] {
+            self.zobrist_key ^= zobrist_piece(piece, color, rank, file);
+            self.pieces[color as usize][piece as usize] &= !bit;
+            self.occupied[color as usize] &= !bit;
+        }
+        if let Some((piece, color)) = square {
+            self.zobrist_key ^= zobrist_piece(piece, color, rank, file);
+            self.pieces[color as usize][piece as usize] |= bit;
+            self.occupied[color as usize] |= bit;
+        }
+        self.board[rank][file] = square;
+    }
+
+    /// Zobrist key of the position, updated incrementally by every move
+    ///
+    /// Two positions share a key when they have the same placement, side to
+    /// move, castling rights, en passant possibility and pockets, which is
+    /// exactly the FIDE notion of a repeated position extended to drops.
+    pub fn zobrist_key(&self) -> u64 {
+        self.zobrist_key ^ self.zobrist_pocket_key()
+    }
+
+    /// Key both players' chains compare after each move
+    ///
+    /// Bughouse pockets fill from the partner board in whatever order its
+    /// messages arrive, so they are left out there.
+    pub fn sync_key(&self) -> u64 {
+        match self.variant {
+            Variant::Bughouse => self.zobrist_key,
+            _ => self.zobrist_key(),
+        }
+    }
+
+    /// Compute the Zobrist key from scratch
+    pub fn compute_zobrist_key(&self) -> u64 {
+        self.compute_board_key() ^ self.zobrist_pocket_key()
+    }
+
+    /// Key of everything but the pockets, the part `zobrist_key` keeps up to date move by move
+    fn compute_board_key(&self) -> u64 {
+        let mut key = self.zobrist_state_key();
+        for rank in 0..8 {
+            for file in 0..8 {
+                if let Some((piece, color)) = self.board[rank][file] {
+                    key ^= zobrist_piece(piece, color, rank, file);
+                }
             }
-            rank += rank_step;
-            file += file_step;
         }
+        if self.active_color == Color::Black {
+            key ^= ZOBRIST_KEYS[ZOBRIST_BLACK_TO_MOVE];
+        }
+        key
+    }
 
-        true
+    /// Zobrist contribution of the pockets, one key per piece held of each kind
+    fn zobrist_pocket_key(&self) -> u64 {
+        let mut key = 0;
+        for color in [Color::White, Color::Black] {
+            for piece in POCKET_PIECES {
+                let kind = ZOBRIST_POCKET + (color as usize * 5 + piece as usize) * ZOBRIST_POCKET_DEPTH;
+                for count in 0..self.pockets[color as usize][piece as usize] as usize {
+                    key ^= ZOBRIST_KEYS[kind + count % ZOBRIST_POCKET_DEPTH];
+                }
+            }
+        }
+        key
+    }
+
+    /// Zobrist contribution of castling rights and the en passant file
+    ///
+    /// The en passant file only counts when a pawn of the side to move stands
+    /// next to the double-pushed pawn, so an unusable en passant square does
+    /// not make otherwise identical positions differ.
+    fn zobrist_state_key(&self) -> u64 {
+        let rights = [
+            self.castling.white_kingside,
+            self.castling.white_queenside,
+            self.castling.black_kingside,
+            self.castling.black_queenside,
+        ];
+        let mut key = rights
+            .iter()
+            .enumerate()
+            .filter(|(_, &right)| right)
+            .fold(0, |key, (i, _)| key ^ ZOBRIST_KEYS[ZOBRIST_CASTLING + i]);
+
+        if let Some((ep_rank, ep_file)) = self.en_passant {
+            // Our pawns that could capture onto the square are those a pawn of
+            // the other color standing there would attack
+            let us = self.active_color;
+            let capturers = bitboard::PAWN_ATTACKS[us.opposite() as usize][bitboard::square(ep_rank, ep_file)];
+            if capturers & self.pieces[us as usize][Piece::Pawn as usize] != 0 {
+                key ^= ZOBRIST_KEYS[ZOBRIST_EN_PASSANT + ep_file];
+            }
+        }
+
+        key
     }
 
     /// Apply a move and return new FEN
     pub fn apply_move(&mut self, from: &str, to: &str, promotion: Option<&str>) -> Result<(), String> {
+        let mv = self.find_legal_move(from, to, promotion)?;
+        self.make_move(&mv);
+
+        Ok(())
+    }
+
+    /// Resolve square names and an optional promotion letter to a legal move
+    pub fn find_legal_move(&self, from: &str, to: &str, promotion: Option<&str>) -> Result<Move, String> {
         let (from_rank, from_file) = Self::parse_square(from)?;
         let (to_rank, to_file) = Self::parse_square(to)?;
 
@@ -381,8 +1409,19 @@ impl Position {
             return Err("Not your piece".to_string());
         }
 
+        let promoted_piece = match promotion {
+            Some(promo) => Some(Self::parse_promotion(promo)?),
+            None => None,
+        };
+
         // Validate move is legal
-        if !self.is_legal_move(from_rank, from_file, to_rank, to_file, piece_data.0, piece_data.1) {
+        let candidates: Vec<Move> = self
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| mv.from == (from_rank, from_file) && mv.to == (to_rank, to_file))
+            .collect();
+
+        if candidates.is_empty() {
             return Err(format!("Illegal move: {} cannot move from {} to {}",
                 match piece_data.0 {
                     Piece::Pawn => "pawn",
@@ -396,116 +1435,570 @@ impl Position {
             ));
         }
 
-        // Check if destination has a piece (capture) BEFORE moving
-        let is_capture = self.board[to_rank][to_file].is_some();
+        // Promotion moves are generated once per piece, so the argument picks one
+        let is_promotion = candidates.iter().any(|mv| mv.promotion.is_some());
+        let mv = match (is_promotion, promoted_piece) {
+            (true, None) => return Err("Promotion piece required for pawn reaching the last rank".to_string()),
+            (false, Some(_)) => return Err("Promotion is only allowed for a pawn reaching the last rank".to_string()),
+            _ => candidates
+                .into_iter()
+                .find(|mv| mv.promotion == promoted_piece)
+                .ok_or("Invalid promotion piece")?,
+        };
 
-        // Move piece
-        self.board[to_rank][to_file] = Some(piece_data);
-        self.board[from_rank][from_file] = None;
+        Ok(mv)
+    }
 
-        // Handle promotion
-        if let Some(promo) = promotion {
-            if piece_data.0 == Piece::Pawn {
-                let promoted_piece = match promo {
-                    "q" => Piece::Queen,
-                    "r" => Piece::Rook,
-                    "b" => Piece::Bishop,
-                    "n" => Piece::Knight,
-                    _ => return Err("Invalid promotion piece".to_string()),
-                };
-                self.board[to_rank][to_file] = Some((promoted_piece, piece_data.1));
-            }
+    /// Drop a pocket piece, given as a letter (`P`, `N`, `B`, `R`, `Q`, either case)
+    pub fn apply_drop(&mut self, piece: &str, square: &str) -> Result<(), String> {
+        let mv = self.find_legal_drop(piece, square)?;
+        self.make_move(&mv);
+
+        Ok(())
+    }
+
+    /// Resolve a piece letter and square to a legal drop
+    pub fn find_legal_drop(&self, piece: &str, square: &str) -> Result<Move, String> {
+        let (piece, _) = Self::parse_pocket_letter(piece)?;
+        let to = Self::parse_square(square)?;
+
+        if !self.has_pockets() {
+            return Err("Drops are only allowed in Crazyhouse and Bughouse".to_string());
+        }
+        if self.pocket_count(self.active_color, piece) == 0 {
+            return Err("Piece not in pocket".to_string());
+        }
+
+        self.legal_moves()
+            .into_iter()
+            .find(|mv| mv.drop == Some(piece) && mv.to == to)
+            .ok_or_else(|| format!("Illegal drop: {}@{}", Self::piece_to_char(piece, Color::White), square))
+    }
+
+    /// Parse a promotion letter (`q`, `r`, `b`, `n`, or `k` for Antichess, either case)
+    pub fn parse_promotion(promo: &str) -> Result<Piece, String> {
+        match promo.to_ascii_lowercase().as_str() {
+            "q" => Ok(Piece::Queen),
+            "r" => Ok(Piece::Rook),
+            "b" => Ok(Piece::Bishop),
+            "n" => Ok(Piece::Knight),
+            "k" => Ok(Piece::King),
+            _ => Err("Invalid promotion piece".to_string()),
         }
+    }
 
-        // Update castling rights (simplified)
-        if piece_data.0 == Piece::King {
-            match piece_data.1 {
-                Color::White => {
-                    self.castling.white_kingside = false;
-                    self.castling.white_queenside = false;
+    /// Detect whether the game has ended in the current position
+    ///
+    /// `history` holds the [`Position::zobrist_key`] of every position
+    /// reached in the game, including the current one. Only automatic endings
+    /// are reported here; threefold repetition and the fifty-move rule must be
+    /// claimed through [`Position::claimable_draw`]. Variant wins, then mate
+    /// and stalemate are checked first, so a winning move on the last allowed
+    /// half-move still wins.
+    pub fn check_game_end(&self, history: &[u64]) -> Option<GameEnd> {
+        let to_move = self.active_color;
+        let mover = to_move.opposite();
+        match self.variant {
+            Variant::Standard | Variant::Crazyhouse | Variant::Bughouse => {}
+            Variant::KingOfTheHill => {
+                if self.pieces[mover as usize][Piece::King as usize] & bitboard::CENTER != 0 {
+                    return Some(GameEnd::KingInCenter { winner: mover });
                 }
-                Color::Black => {
-                    self.castling.black_kingside = false;
-                    self.castling.black_queenside = false;
+            }
+            Variant::ThreeCheck => {
+                if self.checks_given[mover as usize] >= 3 {
+                    return Some(GameEnd::ThirdCheck { winner: mover });
+                }
+            }
+            Variant::Antichess => {
+                if self.occupied[to_move as usize] == 0 {
+                    return Some(GameEnd::AllPiecesLost { winner: to_move });
+                }
+                if self.legal_moves().is_empty() {
+                    return Some(GameEnd::NoMovesLeft { winner: to_move });
                 }
             }
         }
 
-        // Update en passant (simplified - only for pawn double moves)
-        self.en_passant = None;
-        if piece_data.0 == Piece::Pawn {
-            let rank_diff = (to_rank as i32 - from_rank as i32).abs();
-            if rank_diff == 2 {
-                let ep_rank = (from_rank + to_rank) / 2;
-                self.en_passant = Some((ep_rank, from_file));
-            }
+        if self.legal_moves().is_empty() {
+            return Some(if self.is_in_check(self.active_color) {
+                GameEnd::Checkmate { winner: self.active_color.opposite() }
+            } else {
+                GameEnd::Stalemate
+            });
         }
 
-        // Update clocks - check if pawn move or capture
-        if piece_data.0 == Piece::Pawn || is_capture {
-            self.halfmove_clock = 0;
+        if self.is_insufficient_material() {
+            return Some(GameEnd::InsufficientMaterial);
+        }
+
+        if self.repetition_count(history) >= 5 {
+            return Some(GameEnd::FivefoldRepetition);
+        }
+
+        if self.halfmove_clock >= 150 {
+            return Some(GameEnd::SeventyFiveMoveRule);
+        }
+
+        None
+    }
+
+    /// Draw a player may claim in the current position, if any
+    pub fn claimable_draw(&self, history: &[u64]) -> Option<GameEnd> {
+        if self.repetition_count(history) >= 3 {
+            Some(GameEnd::ThreefoldRepetition)
+        } else if self.halfmove_clock >= 100 {
+            Some(GameEnd::FiftyMoveRule)
         } else {
-            self.halfmove_clock += 1;
+            None
         }
+    }
 
-        // Switch active color
-        if self.active_color == Color::Black {
-            self.fullmove_number += 1;
+    /// Number of times the current position occurs in `history`
+    pub fn repetition_count(&self, history: &[u64]) -> usize {
+        let current = self.zobrist_key();
+        history.iter().filter(|&&key| key == current).count()
+    }
+
+    /// Whether neither side can possibly deliver mate
+    ///
+    /// Covers K vs K, K and a single minor piece vs K, and any number of
+    /// bishops that all stand on the same square color.
+    pub fn is_insufficient_material(&self) -> bool {
+        match self.variant {
+            Variant::Standard => {}
+            // A lone king can still walk to the centre, Antichess is won by
+            // losing pieces and captured material returns through the pockets
+            Variant::KingOfTheHill | Variant::Antichess | Variant::Crazyhouse | Variant::Bughouse => return false,
+            // Any piece besides the kings can still give check
+            Variant::ThreeCheck => {
+                let kings = self.pieces[0][Piece::King as usize] | self.pieces[1][Piece::King as usize];
+                return self.all_occupied() == kings;
+            }
         }
-        self.active_color = match self.active_color {
-            Color::White => Color::Black,
-            Color::Black => Color::White,
+
+        let [white, black] = &self.pieces;
+        let heavy = |side: &[Bitboard; 6]| {
+            side[Piece::Pawn as usize] | side[Piece::Rook as usize] | side[Piece::Queen as usize]
         };
+        if heavy(white) | heavy(black) != 0 {
+            return false;
+        }
 
-        Ok(())
+        let knights = white[Piece::Knight as usize] | black[Piece::Knight as usize];
+        let bishops = white[Piece::Bishop as usize] | black[Piece::Bishop as usize];
+        if (knights | bishops).count_ones() <= 1 {
+            return true;
+        }
+
+        knights == 0 && (bishops & bitboard::EVEN_SQUARES == 0 || bishops & !bitboard::EVEN_SQUARES == 0)
     }
 
-    /// Check if the current position is checkmate or stalemate
-    pub fn check_game_end(&self) -> (bool, bool) {
-        // Simplified game end detection
-        // In a full implementation, this would check for checkmate/stalemate
-        // For now, we check for insufficient material or 50-move rule
+    /// Whether `color` could still win, judged on its own material
+    ///
+    /// Used when the other side runs out of time: a bare king, or a king and
+    /// a single minor piece, cannot mate, so the flag fall is only a draw.
+    pub fn has_mating_material(&self, color: Color) -> bool {
+        let side = &self.pieces[color as usize];
+        let pieces = self.occupied[color as usize] & !side[Piece::King as usize];
+        match self.variant {
+            Variant::Standard => {}
+            // The king itself can win, or the variant is won by losing material
+            Variant::KingOfTheHill | Variant::Antichess => return true,
+            Variant::ThreeCheck => return pieces != 0,
+            // Material can still arrive through the pocket
+            Variant::Crazyhouse | Variant::Bughouse => return true,
+        }
 
-        let is_stalemate = self.halfmove_clock >= 100; // 50-move rule
-        let is_checkmate = false; // Requires full move generation
+        let minors = side[Piece::Knight as usize] | side[Piece::Bishop as usize];
+        pieces & !minors != 0 || minors.count_ones() >= 2
+    }
 
-        (is_checkmate, is_stalemate)
+    /// Chess960 starting position number `index` (0-959) in X-FEN
+    ///
+    /// Uses Scharnagl's numbering, so position 518 is the standard setup.
+    pub fn chess960_fen(index: u32) -> String {
+        // Knight placements among the five squares left after bishops and queen
+        const KNIGHTS: [(usize, usize); 10] =
+            [(0, 1), (0, 2), (0, 3), (0, 4), (1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)];
+
+        let mut n = (index % 960) as usize;
+        let mut rank: [Option<Piece>; 8] = [None; 8];
+        rank[n % 4 * 2 + 1] = Some(Piece::Bishop);
+        n /= 4;
+        rank[n % 4 * 2] = Some(Piece::Bishop);
+        n /= 4;
+
+        let free = |rank: &[Option<Piece>; 8]| (0..8).filter(|&f| rank[f].is_none()).collect::<Vec<_>>();
+        let queen = free(&rank)[n % 6];
+        rank[queen] = Some(Piece::Queen);
+        n /= 6;
+
+        let empty = free(&rank);
+        let (first, second) = KNIGHTS[n];
+        rank[empty[first]] = Some(Piece::Knight);
+        rank[empty[second]] = Some(Piece::Knight);
+
+        // The king always lands between the two rooks
+        for (piece, file) in [Piece::Rook, Piece::King, Piece::Rook].into_iter().zip(free(&rank)) {
+            rank[file] = Some(piece);
+        }
+
+        let black: String = rank
+            .iter()
+            .map(|piece| Self::piece_to_char(piece.expect("Every file is filled"), Color::Black))
+            .collect();
+        format!("{}/pppppppp/8/8/8/8/PPPPPPPP/{} w KQkq - 0 1", black, black.to_ascii_uppercase())
     }
 
-    /// Convert move to Standard Algebraic Notation (simplified)
-    pub fn to_san(&self, from: &str, to: &str) -> String {
-        // Simplified SAN - just return the move in algebraic notation
-        // Full SAN would include piece letter, captures, checks, etc.
+    /// Play a PGN game or bare movetext and return the position it reaches
+    ///
+    /// Tag pairs are skipped except `FEN`, which sets the starting position,
+    /// and `Variant`, which selects Chess960 castling or another rule set.
+    /// Comments, variations, NAGs, move numbers and the result are ignored;
+    /// every move must be legal SAN in the position it is played from.
+    pub fn from_pgn(pgn: &str) -> Result<Position, String> {
+        Self::from_pgn_variant(pgn, Variant::Standard)
+    }
 
-        if let Ok((from_rank, from_file)) = Self::parse_square(from) {
-            if let Some((piece, _color)) = self.board[from_rank][from_file] {
-                let piece_char = match piece {
-                    Piece::Pawn => "",
-                    Piece::Knight => "N",
-                    Piece::Bishop => "B",
-                    Piece::Rook => "R",
-                    Piece::Queen => "Q",
-                    Piece::King => "K",
-                };
+    /// Play a PGN game under `variant` unless its `Variant` tag names another
+    pub fn from_pgn_variant(pgn: &str, variant: Variant) -> Result<Position, String> {
+        let mut fen = None;
+        let mut variant = variant;
+        let mut chess960 = false;
+        let mut movetext = String::new();
+        for line in pgn.lines() {
+            let line = line.trim();
+            if let Some(tag) = line.strip_prefix('[') {
+                if let Some(value) = Self::pgn_tag_value(tag, "FEN") {
+                    fen = Some(value);
+                }
+                if let Some(value) = Self::pgn_tag_value(tag, "Variant") {
+                    chess960 = value.eq_ignore_ascii_case("chess960");
+                    variant = Self::pgn_variant(&value).unwrap_or(variant);
+                }
+            } else if !line.starts_with('%') {
+                movetext.push_str(line);
+                movetext.push('\n');
+            }
+        }
 
-                // Check if capture
-                let capture = if let Ok((to_rank, to_file)) = Self::parse_square(to) {
-                    self.board[to_rank][to_file].is_some()
-                } else {
-                    false
-                };
+        let fen = fen.unwrap_or_else(|| Self::starting_fen(variant));
+        let mut position = Position::from_fen_variant(&fen, variant).map_err(|e| e.to_string())?;
+        if chess960 {
+            position.enable_chess960();
+        }
+        for san in Self::pgn_moves(&movetext)? {
+            let mv = position
+                .parse_san(&san)
+                .map_err(|e| format!("Move {}: {}", position.fullmove_number, e))?;
+            position.make_move(&mv);
+        }
+        Ok(position)
+    }
 
-                if piece == Piece::Pawn && capture {
-                    return format!("{}x{}", from.chars().nth(0).unwrap(), to);
-                } else if capture {
-                    return format!("{}x{}", piece_char, to);
-                } else {
-                    return format!("{}{}", piece_char, to);
+    /// Parse a starting position for `variant` given either as FEN or as PGN
+    ///
+    /// Text whose first field is a board with `/` between ranks is FEN and
+    /// reports its `FenError`; PGN movetext never starts that way.
+    pub fn from_fen_or_pgn(text: &str, variant: Variant) -> Result<Position, String> {
+        let text = text.trim();
+        let is_fen = text.split_whitespace().next().is_some_and(|board| board.contains('/'));
+        if is_fen {
+            return Position::from_fen_variant(text, variant).map_err(|e| e.to_string());
+        }
+        Position::from_pgn_variant(text, variant)
+    }
+
+    /// Starting position of a new game from a FEN or PGN prefix; the game must still be undecided
+    pub fn parse_starting_position(text: &str, variant: Variant) -> Result<Position, String> {
+        let position = Position::from_fen_or_pgn(text, variant)?;
+        if position.variant() != variant {
+            return Err("the PGN Variant tag does not match the game variant".to_string());
+        }
+        if position.check_game_end(&[position.zobrist_key()]).is_some() {
+            return Err("the game is already over in this position".to_string());
+        }
+        Ok(position)
+    }
+
+    /// Rule set named by a PGN `Variant` tag, as written by lichess
+    fn pgn_variant(value: &str) -> Option<Variant> {
+        match value.to_ascii_lowercase().as_str() {
+            "standard" | "chess960" => Some(Variant::Standard),
+            "king of the hill" => Some(Variant::KingOfTheHill),
+            "three-check" => Some(Variant::ThreeCheck),
+            "antichess" => Some(Variant::Antichess),
+            "crazyhouse" => Some(Variant::Crazyhouse),
+            "bughouse" => Some(Variant::Bughouse),
+            _ => None,
+        }
+    }
+
+    /// Value of a `[Name "value"]` tag pair (opening bracket already removed)
+    fn pgn_tag_value(tag: &str, name: &str) -> Option<String> {
+        let (tag_name, rest) = tag.trim_end().strip_suffix(']')?.split_once(char::is_whitespace)?;
+        if tag_name != name {
+            return None;
+        }
+        let quoted = rest.trim().strip_prefix('"')?.strip_suffix('"')?;
+        Some(quoted.replace("\\\"", "\"").replace("\\\\", "\\"))
+    }
+
+    /// SAN tokens of the main line of PGN movetext
+    fn pgn_moves(movetext: &str) -> Result<Vec<String>, String> {
+        let mut moves = Vec::new();
+        let mut token = String::new();
+        let mut variation_depth = 0usize;
+        let mut chars = movetext.chars();
+
+        let mut finish_token = |token: &mut String, depth: usize| {
+            let text = std::mem::take(token);
+            // Move numbers may be glued to the move, as in `12.Nf3` or `12...Nf6`
+            let after_digits = text.trim_start_matches(|c: char| c.is_ascii_digit());
+            let san = if after_digits.len() < text.len() && after_digits.starts_with('.') {
+                after_digits.trim_start_matches('.')
+            } else {
+                text.as_str()
+            };
+            let is_result = matches!(san, "1-0" | "0-1" | "1/2-1/2" | "*");
+            if depth == 0 && !san.is_empty() && !san.starts_with('$') && !is_result {
+                moves.push(san.to_string());
+            }
+        };
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' => {
+                    finish_token(&mut token, variation_depth);
+                    if !chars.by_ref().any(|c| c == '}') {
+                        return Err("Unterminated PGN comment".to_string());
+                    }
+                }
+                ';' => {
+                    finish_token(&mut token, variation_depth);
+                    chars.by_ref().find(|&c| c == '\n');
                 }
+                '(' => {
+                    finish_token(&mut token, variation_depth);
+                    variation_depth += 1;
+                }
+                ')' => {
+                    finish_token(&mut token, variation_depth);
+                    variation_depth = variation_depth
+                        .checked_sub(1)
+                        .ok_or_else(|| "Unbalanced PGN variation".to_string())?;
+                }
+                c if c.is_whitespace() => finish_token(&mut token, variation_depth),
+                c => token.push(c),
             }
         }
+        finish_token(&mut token, variation_depth);
+
+        if variation_depth != 0 {
+            return Err("Unbalanced PGN variation".to_string());
+        }
+        Ok(moves)
+    }
+
+    /// Resolve a UCI move (`e2e4`, `e7e8q`, `N@f3`) against the legal moves
+    ///
+    /// In Chess960 castling is encoded as king-takes-rook, but the standard
+    /// `e1g1`/`e1c1` form is accepted too when it is not an ordinary king move.
+    pub fn parse_uci(&self, uci: &str) -> Result<Move, String> {
+        let uci = uci.trim();
+        if !uci.is_ascii() || !(4..=5).contains(&uci.len()) {
+            return Err(format!("Invalid UCI move: {}", uci));
+        }
+        if let Some((piece, square)) = uci.split_once('@') {
+            return self.find_legal_drop(piece, square);
+        }
+
+        let (from, to) = (&uci[0..2], &uci[2..4]);
+        self.find_legal_move(from, to, uci.get(4..).filter(|p| !p.is_empty()))
+            .or_else(|err| self.find_standard_castle(from, to).ok_or(err))
+    }
+
+    /// Chess960 castle written as the king landing on the g or c file
+    fn find_standard_castle(&self, from: &str, to: &str) -> Option<Move> {
+        let from = Self::parse_square(from).ok()?;
+        let (to_rank, to_file) = Self::parse_square(to).ok()?;
+        let home_rank = if self.active_color == Color::White { 7 } else { 0 };
+        if !self.chess960 || from.0 != home_rank || to_rank != home_rank || !matches!(to_file, 2 | 6) {
+            return None;
+        }
+        self.legal_moves()
+            .into_iter()
+            .find(|mv| mv.from == from && self.castling_side(mv) == Some(to_file == 6))
+    }
+
+    /// Resolve a SAN move (`e4`, `Nbd7`, `exd6`, `O-O`, `e8=Q+`, `N@f3`) against the legal moves
+    ///
+    /// Check and annotation suffixes are ignored. Input that matches no legal
+    /// move, or more than one, is rejected.
+    pub fn parse_san(&self, san: &str) -> Result<Move, String> {
+        let text = san.trim().trim_end_matches(['+', '#', '!', '?']);
+        if text.is_empty() || !text.is_ascii() {
+            return Err(format!("Invalid SAN move: {}", san));
+        }
+
+        // Drops, `N@f3`, with the pawn letter optional
+        if let Some((piece, square)) = text.split_once('@') {
+            return self.find_legal_drop(if piece.is_empty() { "P" } else { piece }, square);
+        }
+
+        let legal = self.legal_moves();
+        let is_piece = |mv: &Move, piece: Piece| self.board[mv.from.0][mv.from.1].map(|(p, _)| p) == Some(piece);
+
+        // Castling, also accepting zeros
+        let castle_side = match text {
+            "O-O" | "0-0" => Some(true),
+            "O-O-O" | "0-0-0" => Some(false),
+            _ => None,
+        };
+        if let Some(kingside) = castle_side {
+            return legal
+                .into_iter()
+                .find(|mv| self.castling_side(mv) == Some(kingside))
+                .ok_or_else(|| format!("Illegal move: {}", san));
+        }
+
+        // Peel off the promotion suffix (`=Q`, or a bare `Q` after the square)
+        let mut body = text;
+        let mut promotion = None;
+        if let Some(last) = body.chars().last().filter(|c| "NBRQK".contains(*c)) {
+            if body.len() > 2 {
+                promotion = Some(Self::parse_promotion(&last.to_string())?);
+                body = body[..body.len() - 1].trim_end_matches('=');
+            }
+        }
+
+        // Optional piece letter, then disambiguation, optional capture, target square
+        let (piece, rest) = match body.chars().next() {
+            Some(c @ ('N' | 'B' | 'R' | 'Q' | 'K')) => (Self::parse_piece(c).map_err(|e| e.to_string())?.0, &body[1..]),
+            _ => (Piece::Pawn, body),
+        };
+        if rest.len() < 2 {
+            return Err(format!("Invalid SAN move: {}", san));
+        }
+        let target = Self::parse_square(&rest[rest.len() - 2..])
+            .map_err(|_| format!("Invalid SAN move: {}", san))?;
+        let qualifier = rest[..rest.len() - 2].trim_end_matches('x');
+
+        let mut from_file = None;
+        let mut from_rank = None;
+        for c in qualifier.chars() {
+            match c {
+                'a'..='h' if from_file.is_none() && from_rank.is_none() => {
+                    from_file = Some((c as u8 - b'a') as usize)
+                }
+                '1'..='8' if from_rank.is_none() => from_rank = Some((b'8' - c as u8) as usize),
+                _ => return Err(format!("Invalid SAN move: {}", san)),
+            }
+        }
+
+        let matches: Vec<Move> = legal
+            .into_iter()
+            .filter(|mv| {
+                is_piece(mv, piece)
+                    && self.castling_side(mv).is_none()
+                    && mv.to == target
+                    && mv.promotion == promotion
+                    && from_file.is_none_or(|file| mv.from.1 == file)
+                    && from_rank.is_none_or(|rank| mv.from.0 == rank)
+            })
+            .collect();
+
+        match matches.as_slice() {
+            [mv] => Ok(*mv),
+            [] => Err(format!("Illegal move: {}", san)),
+            _ => Err(format!("Ambiguous move: {}", san)),
+        }
+    }
+
+    /// Convert move to Standard Algebraic Notation
+    ///
+    /// Falls back to the raw squares when the move is not legal here.
+    pub fn to_san(&self, from: &str, to: &str, promotion: Option<&str>) -> String {
+        match self.find_legal_move(from, to, promotion) {
+            Ok(mv) => self.move_to_san(&mv),
+            Err(_) => format!("{}{}", from, to),
+        }
+    }
+
+    /// Standard Algebraic Notation for a legal move in this position
+    ///
+    /// Emits castling as `O-O`/`O-O-O` and drops as `N@f3`, disambiguates by
+    /// file, then rank, then both when another piece of the same kind can
+    /// reach the target, and appends `=Q` style promotions and `+`/`#` suffixes.
+    pub fn move_to_san(&self, mv: &Move) -> String {
+        let (from_rank, from_file) = mv.from;
+        let (to_rank, to_file) = mv.to;
+        let piece = match (mv.drop, self.board[from_rank][from_file]) {
+            (Some(piece), _) | (None, Some((piece, _))) => piece,
+            (None, None) => return mv.to_uci(),
+        };
+
+        let mut san = String::new();
+
+        if mv.drop.is_some() {
+            san.push_str(&mv.to_uci());
+        } else if let Some(kingside) = self.castling_side(mv) {
+            san.push_str(if kingside { "O-O" } else { "O-O-O" });
+        } else {
+            let is_capture = self.board[to_rank][to_file].is_some()
+                || (piece == Piece::Pawn && from_file != to_file);
+
+            if piece == Piece::Pawn {
+                if is_capture {
+                    san.push((b'a' + from_file as u8) as char);
+                }
+            } else {
+                san.push(Self::piece_to_char(piece, Color::White));
+
+                // Other pieces of the same kind that can also reach the target
+                let rivals: Vec<(usize, usize)> = self
+                    .legal_moves()
+                    .into_iter()
+                    .filter(|other| {
+                        other.to == mv.to
+                            && other.from != mv.from
+                            && self.board[other.from.0][other.from.1].map(|(p, _)| p) == Some(piece)
+                    })
+                    .map(|other| other.from)
+                    .collect();
+
+                if !rivals.is_empty() {
+                    let file_unique = rivals.iter().all(|&(_, file)| file != from_file);
+                    let rank_unique = rivals.iter().all(|&(rank, _)| rank != from_rank);
+                    let square = Self::square_name(from_rank, from_file);
+                    if file_unique {
+                        san.push_str(&square[..1]);
+                    } else if rank_unique {
+                        san.push_str(&square[1..]);
+                    } else {
+                        san.push_str(&square);
+                    }
+                }
+            }
+
+            if is_capture {
+                san.push('x');
+            }
+            san.push_str(&Self::square_name(to_rank, to_file));
+
+            if let Some(promoted) = mv.promotion {
+                san.push('=');
+                san.push(Self::piece_to_char(promoted, Color::White));
+            }
+        }
+
+        let mut next = self.clone();
+        next.make_move(mv);
+        if next.is_in_check(next.active_color) {
+            san.push(if next.legal_moves().is_empty() { '#' } else { '+' });
+        }
 
-        // Fallback
-        format!("{}{}", from, to)
+        san
     }
 }
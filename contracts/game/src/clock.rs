@@ -0,0 +1,186 @@
+//! Game clocks
+//!
+//! Each side's remaining time is stored on the `ChessGame` in microseconds.
+//! Only the side to move's clock runs, counting from `clock_started_at`; a
+//! move stops it, deducts the elapsed time and credits the increment or
+//! delay of the current stage. Finishing a stage's moves adds the next
+//! stage's time.
+
+use crate::chess_logic::{Color, Position};
+use abi::{ChessGame, ClockMode, GameError, GameResult, Termination, TimeControl, TimeStage};
+
+const MICROS_PER_SECOND: u64 = 1_000_000;
+
+/// Check a time control can be played: every stage but the last needs a move count
+pub fn validate(control: &TimeControl) -> Result<(), String> {
+    let Some(first) = control.stages.first() else {
+        return Ok(());
+    };
+    if first.seconds == 0 {
+        return Err("First stage has no time".to_string());
+    }
+    let last = control.stages.len() - 1;
+    if control.stages[..last].iter().any(|stage| stage.moves == 0) {
+        return Err("Only the last stage may run to the end of the game".to_string());
+    }
+    Ok(())
+}
+
+/// Whether the game is played with a clock
+pub fn is_timed(game: &ChessGame) -> bool {
+    !game.time_control.stages.is_empty()
+}
+
+/// Set both clocks to the first stage's time and start the clock of the side to move
+pub fn start(game: &mut ChessGame, now: u64) {
+    let base = game.time_control.stages.first().map_or(0, |stage| seconds(stage.seconds));
+    game.white_time_left = base;
+    game.black_time_left = base;
+    game.clock_started_at = now;
+}
+
+/// Time `color` has left at `now`, running down only while it is to move
+///
+/// Under a simple delay the clock only starts once the delay has passed.
+pub fn time_left(game: &ChessGame, color: Color, to_move: Color, now: u64) -> u64 {
+    let stored = stored_time(game, color);
+    if !is_timed(game) || color != to_move || game.clock_started_at == 0 {
+        return stored;
+    }
+    let mut elapsed = now.saturating_sub(game.clock_started_at);
+    if game.time_control.mode == ClockMode::SimpleDelay {
+        elapsed = elapsed.saturating_sub(seconds(current_stage(game).bonus));
+    }
+    stored.saturating_sub(elapsed)
+}
+
+/// Whether `to_move` has run out of time at `now`
+///
+/// Only a started clock can fall; the side not to move keeps its time.
+pub fn has_flagged(game: &ChessGame, to_move: Color, now: u64) -> bool {
+    is_timed(game) && game.clock_started_at != 0 && time_left(game, to_move, to_move, now) == 0
+}
+
+/// Check `claimant` may claim a win on time at `now` while `to_move` is to move
+///
+/// Only the opponent's running clock can be claimed, and only once it has
+/// run out. Returns the side that flagged.
+pub fn claim_timeout(game: &ChessGame, claimant: Color, to_move: Color, now: u64) -> Result<Color, GameError> {
+    if claimant == to_move {
+        return Err(GameError::OwnClock);
+    }
+    if !has_flagged(game, to_move, now) {
+        return Err(GameError::OpponentHasTime);
+    }
+    Ok(to_move)
+}
+
+/// Stop the clock of `color` for a move made at `now`
+///
+/// Returns `false` when its time had already run out, leaving the clock at
+/// zero; otherwise the stage's bonus is credited and the opponent's clock
+/// starts. Must run before `move_count` counts the move.
+pub fn record_move(game: &mut ChessGame, color: Color, now: u64) -> bool {
+    let left = time_left(game, color, color, now);
+    if left == 0 {
+        set_time(game, color, 0);
+        return false;
+    }
+
+    let (index, ends_stage) = stage_at(&game.time_control, move_number(game));
+    let stage = &game.time_control.stages[index];
+    let bonus = match game.time_control.mode {
+        ClockMode::Increment => seconds(stage.bonus),
+        ClockMode::SimpleDelay => 0,
+        ClockMode::BronsteinDelay => now.saturating_sub(game.clock_started_at).min(seconds(stage.bonus)),
+    };
+
+    // The move that completes a stage brings in the next one's time
+    let last = game.time_control.stages.len() - 1;
+    let next_stage = if ends_stage { seconds(game.time_control.stages[(index + 1).min(last)].seconds) } else { 0 };
+
+    set_time(game, color, left + bonus + next_stage);
+    game.clock_started_at = now;
+    true
+}
+
+/// Run the opponent's clock for a move it reports making at `moved_at` with `reported` time left
+///
+/// Our copy recomputes the clock from its own `clock_started_at`, and takes
+/// the reported time only when it is no more than that. A move stamped
+/// before the clock started, or after it ran out, leaves the clock as it was.
+pub fn receive_move(game: &mut ChessGame, color: Color, reported: u64, moved_at: u64) -> Result<(), String> {
+    if moved_at < game.clock_started_at {
+        return Err("Move made before the clock started".to_string());
+    }
+    if time_left(game, color, color, moved_at) == 0 {
+        return Err("Move made after the flag fell".to_string());
+    }
+    record_move(game, color, moved_at);
+    set_time(game, color, reported.min(stored_time(game, color)));
+    Ok(())
+}
+
+/// Result and termination when `flagged` runs out of time
+///
+/// The opponent wins unless it has too little material to ever mate.
+pub fn flag_result(position: &Position, flagged: Color) -> (GameResult, Termination) {
+    let winner = flagged.opposite();
+    if !position.has_mating_material(winner) {
+        return (GameResult::Draw, Termination::TimeoutVsInsufficientMaterial);
+    }
+    let result = match winner {
+        Color::White => GameResult::WhiteWins,
+        Color::Black => GameResult::BlackWins,
+    };
+    (result, Termination::Timeout)
+}
+
+/// Stage index of a player's `move_number` (from 1), and whether it is that stage's last move
+pub fn stage_at(control: &TimeControl, move_number: u32) -> (usize, bool) {
+    let last = control.stages.len().saturating_sub(1);
+    let mut remaining = move_number.max(1);
+    for (index, stage) in control.stages.iter().enumerate() {
+        if stage.moves == 0 {
+            return (index, false);
+        }
+        if index == last {
+            // A final stage with a move count repeats for the rest of the game
+            let within = (remaining - 1) % stage.moves + 1;
+            return (index, within == stage.moves);
+        }
+        if remaining <= stage.moves {
+            return (index, remaining == stage.moves);
+        }
+        remaining -= stage.moves;
+    }
+    (last, false)
+}
+
+/// The side to move's coming move number; each side has made `move_count / 2` moves before it
+fn move_number(game: &ChessGame) -> u32 {
+    game.move_count / 2 + 1
+}
+
+fn current_stage(game: &ChessGame) -> &TimeStage {
+    let (index, _) = stage_at(&game.time_control, move_number(game));
+    &game.time_control.stages[index]
+}
+
+fn seconds(seconds: u32) -> u64 {
+    u64::from(seconds) * MICROS_PER_SECOND
+}
+
+fn stored_time(game: &ChessGame, color: Color) -> u64 {
+    match color {
+        Color::White => game.white_time_left,
+        Color::Black => game.black_time_left,
+    }
+}
+
+fn set_time(game: &mut ChessGame, color: Color, time: u64) {
+    match color {
+        Color::White => game.white_time_left = time,
+        Color::Black => game.black_time_left = time,
+    }
+}
@@ -1,17 +1,25 @@
 #![cfg_attr(target_arch = "wasm32", no_main)]
 
+mod bitboard;
 mod chess_logic;
+mod clock;
+mod draw;
+mod invite;
+mod pgn;
+mod resign;
 mod state;
+mod sync;
 #[cfg(test)]
 mod tests;
 
-use self::chess_logic::Position;
+use self::chess_logic::{Color, Position};
 use self::state::GameState;
 use abi::{
-    ChessGame, ChessMove, GameResult, GameStatus, STARTING_FEN,
+    ChessGame, ChessMove, GameError, GameResult, GameStatus, MoveNotation, PartnerBoard, Termination, TimeControl, Variant,
 };
-use game::{GameAbi, GameEvent, Message, Operation, OperationResult};
-use linera_sdk::linera_base_types::{ChainId, WithContractAbi};
+use betting::{BettingAbi, Operation as BettingOperation};
+use game::{GameAbi, GameEvent, GameIdentity, Message, Operation, OperationResult};
+use linera_sdk::linera_base_types::{ChainId, StreamUpdate, WithContractAbi};
 use linera_sdk::views::{RootView, View};
 use linera_sdk::{Contract, ContractRuntime};
 use std::str::FromStr;
@@ -22,6 +30,8 @@ const STREAM_NAME: &[u8] = b"game_events";
 pub struct GameContract {
     state: GameState,
     runtime: ContractRuntime<Self>,
+    /// Player whose forwarded operation a game chain is executing
+    acting_for: Option<ChainId>,
 }
 
 linera_sdk::contract!(GameContract);
@@ -40,7 +50,7 @@ impl Contract for GameContract {
         let state = GameState::load(runtime.root_view_storage_context())
             .await
             .expect("Failed to load state");
-        GameContract { state, runtime }
+        GameContract { state, runtime, acting_for: None }
     }
 
     async fn instantiate(&mut self, _argument: Self::InstantiationArgument) {
@@ -51,24 +61,129 @@ impl Contract for GameContract {
         &mut self,
         operation: Operation,
     ) -> OperationResult {
+        // Actions on a hosted game run on its game chain, which the player chain only mirrors
+        if let Some(game_id) = operation.game_id().map(str::to_string) {
+            if let Some(game_chain) = self.hosting_chain(&game_id).await {
+                if game_chain != self.runtime.chain_id() {
+                    self.state.hosted_rejections.remove(&game_id).expect("Failed to clear rejection");
+                    self.runtime
+                        .prepare_message(Message::HostedOperation { operation })
+                        .with_authentication()
+                        .with_tracking()
+                        .send_to(game_chain);
+                    return OperationResult::Forwarded { game_id };
+                }
+            }
+
+            // An invite past its TTL is closed by the next operation on it
+            if self.close_expired_invite(&game_id).await {
+                match operation {
+                    Operation::AcceptGame { .. } => {
+                        return OperationResult::AcceptRejected { reason: GameError::InviteExpired };
+                    }
+                    Operation::DeclineGame { .. } | Operation::CancelGame { .. } => {
+                        return OperationResult::GameCancelled { game_id };
+                    }
+                    _ => {}
+                }
+            }
+        }
+
         match operation {
             Operation::CreateGame {
                 opponent_chain,
-                time_control: _,
+                time_control,
+                preset,
+                starting_position,
+                chess960,
+                variant,
+                game_chain,
             } => {
-                let game_id = self.state.generate_game_id().await;
+                // Chess960 back ranks are only drawn for standard rules
+                if chess960 && variant != Variant::Standard {
+                    return OperationResult::CreateRejected { reason: GameError::Chess960RequiresStandard };
+                }
+
+                // A game chain must be a third chain, and Bughouse boards stay on the player chains
+                if let Some(game_chain) = game_chain {
+                    if game_chain == self.runtime.chain_id() || game_chain == opponent_chain {
+                        return OperationResult::CreateRejected {
+                            reason: GameError::InvalidGameChain("a player's chain cannot host the game".to_string()),
+                        };
+                    }
+                    if variant == Variant::Bughouse {
+                        return OperationResult::CreateRejected {
+                            reason: GameError::InvalidGameChain("Bughouse boards are played on the player chains".to_string()),
+                        };
+                    }
+                }
+
+                // A preset or explicit stages, untimed when neither is given
+                let time_control = match (time_control, preset) {
+                    (Some(_), Some(_)) => {
+                        return OperationResult::CreateRejected {
+                            reason: GameError::InvalidTimeControl("give either a time control or a preset, not both".to_string()),
+                        };
+                    }
+                    (Some(time_control), None) => time_control,
+                    (None, Some(preset)) => preset.time_control(),
+                    (None, None) => TimeControl::default(),
+                };
+                if let Err(e) = clock::validate(&time_control) {
+                    return OperationResult::CreateRejected { reason: GameError::InvalidTimeControl(e) };
+                }
+
+                // Resolve the optional FEN or PGN prefix before anything is stored
+                let mut start = match starting_position.as_deref() {
+                    Some(text) => match Position::parse_starting_position(text, variant) {
+                        Ok(position) => position,
+                        Err(e) => {
+                            return OperationResult::CreateRejected { reason: GameError::InvalidStartingPosition(e) };
+                        }
+                    },
+                    None => Position::from_fen_variant(&Position::starting_fen(variant), variant)
+                        .expect("Invalid starting FEN"),
+                };
+                if chess960 {
+                    start.enable_chess960();
+                }
+                let start_fen = start.to_fen();
+
+                let identity = self.state.generate_game_id(self.runtime.chain_id()).await;
+                let game_id = identity.id();
 
                 let timestamp = self.runtime.system_time().micros();
+
+                // A Chess960 game without a given start draws its back rank at
+                // acceptance; our share of the seed travels with the invite. The
+                // other share is fixed by the game, so this chain chooses the start
+                let seed = (chess960 && starting_position.is_none()).then_some(timestamp);
+                if let Some(seed) = seed {
+                    self.state.chess960_seeds.insert(&game_id, seed).expect("Failed to store Chess960 seed");
+                }
+
                 let game = ChessGame {
                     id: game_id.clone(),
                     white_player: self.runtime.chain_id().to_string(),
                     black_player: opponent_chain.to_string(),
-                    fen: STARTING_FEN.to_string(),
-                    current_fen: STARTING_FEN.to_string(),
+                    fen: start_fen.clone(),
+                    current_fen: start_fen.clone(),
+                    zobrist_key: format!("{:016x}", start.zobrist_key()),
+                    desynced: false,
                     moves: Vec::new(),
                     move_count: 0,
                     status: GameStatus::Pending,
                     result: None,
+                    termination: None,
+                    draw_offer: None,
+                    time_control: time_control.clone(),
+                    white_time_left: 0,
+                    black_time_left: 0,
+                    clock_started_at: 0,
+                    chess960,
+                    variant,
+                    bughouse_partner: None,
+                    game_chain: game_chain.map(|chain| chain.to_string()),
                     created_at: timestamp,
                     updated_at: timestamp,
                 };
@@ -80,13 +195,35 @@ impl Contract for GameContract {
 
                 // Store initial position
                 self.state
-                    .update_position(&game_id, STARTING_FEN.to_string())
+                    .update_position(&game_id, start_fen.clone())
                     .await.expect("Failed to store initial position");
+                self.state
+                    .add_position_key(&game_id, start.zobrist_key())
+                    .await.expect("Failed to record initial position");
+
+                // Hand the canonical copy to the game chain and mirror its events from now on
+                if let Some(game_chain) = game_chain {
+                    self.subscribe_to_game_chain(game_chain);
+                    let betting_chain = *self.state.betting_chain.get();
+                    let hub_chain = *self.state.hub_chain.get();
+                    self.runtime
+                        .prepare_message(Message::HostGame { game: game.clone(), seed, betting_chain, hub_chain })
+                        .with_authentication()
+                        .with_tracking()
+                        .send_to(game_chain);
+                }
 
                 // Send invitation to opponent - use tracking to ensure delivery
                 self.runtime
                     .prepare_message(Message::GameInvite {
-                        game_id: game_id.clone(),
+                        identity,
+                        time_control,
+                        fen: start_fen,
+                        chess960,
+                        variant,
+                        seed,
+                        game_chain,
+                        created_at: timestamp,
                     })
                     .with_authentication()
                     .with_tracking()
@@ -104,6 +241,19 @@ impl Contract for GameContract {
                         .send_to(hub_chain);
                 }
 
+                // Tell the betting chain which chains may call the game off
+                if let Some(betting_chain) = *self.state.betting_chain.get() {
+                    self.runtime
+                        .prepare_message(Message::OpenBetting {
+                            game_id: game_id.clone(),
+                            black_player: opponent_chain,
+                            game_chain,
+                        })
+                        .with_authentication()
+                        .with_tracking()
+                        .send_to(betting_chain);
+                }
+
                 // Emit event
                 let white_player_chain = self.runtime.chain_id().to_string();
                 self.runtime.emit(STREAM_NAME.into(), &GameEvent::GameCreated {
@@ -125,56 +275,49 @@ impl Contract for GameContract {
                         // Game not found on this chain - this can happen if:
                         // 1. The GameInvite message hasn't been processed yet
                         // 2. The game_id is invalid
-                        // Return an error result instead of panicking
-                        return OperationResult::GameAccepted {
-                            game_id: format!("ERROR: Game {} not found on this chain. The game invitation may not have been received yet.", game_id)
-                        };
+                        return OperationResult::AcceptRejected { reason: GameError::GameNotFound(game_id) };
                     }
                 };
 
                 // Verify caller is the invited player (black player)
-                let caller_chain = self.runtime.chain_id().to_string();
+                let caller_chain = self.caller();
                 if caller_chain != game.black_player {
-                    return OperationResult::GameAccepted {
-                        game_id: format!("ERROR: Not invited to this game. Caller: {}, Expected: {}",
-                            caller_chain.chars().take(16).collect::<String>(),
-                            game.black_player.chars().take(16).collect::<String>())
-                    };
+                    return OperationResult::AcceptRejected { reason: GameError::NotInvited };
                 }
 
                 // Verify game is pending
                 if game.status != GameStatus::Pending {
-                    return OperationResult::GameAccepted {
-                        game_id: format!("ERROR: Game already {} (not pending)",
-                            match game.status {
-                                GameStatus::Active => "active",
-                                GameStatus::Finished => "finished",
-                                GameStatus::Waiting => "waiting",
-                                _ => "unknown"
-                            })
-                    };
+                    return OperationResult::AcceptRejected { reason: GameError::GameNotPending };
+                }
+
+                // Draw the Chess960 back rank from the creator's share and one the game fixes
+                let creator_seed = self.state.take_chess960_seed(&game_id).await.expect("Failed to get Chess960 seed");
+                let seed = creator_seed.map(|_| Self::chess960_share(&game));
+                if let (Some(creator_seed), Some(seed)) = (creator_seed, seed) {
+                    self.set_chess960_start(&mut game, creator_seed, seed).await;
                 }
 
-                // Update game status to Active
+                // Update game status to Active and start the clock of the side to move
+                let now = self.runtime.system_time().micros();
                 game.status = GameStatus::Active;
-                game.updated_at = self.runtime.system_time().micros();
+                game.updated_at = now;
+                clock::start(&mut game, now);
                 self.state.update_game(game.clone()).await.expect("Failed to update game");
 
                 // Notify white player that game has started
-                let white_chain = ChainId::from_str(&game.white_player).expect("Invalid white player ChainId");
-                self.runtime
-                    .prepare_message(Message::GameStarted {
-                        game_id: game_id.clone(),
-                    })
-                    .with_authentication()
-                    .with_tracking()
-                    .send_to(white_chain);
+                self.notify_opponent(&game, &caller_chain, Message::GameStarted {
+                    game_id: game_id.clone(),
+                    seed,
+                    started_at: now,
+                });
 
                 // Notify betting chain that game has started
                 if let Some(betting_chain) = *self.state.betting_chain.get() {
                     self.runtime
                         .prepare_message(Message::GameStarted {
                             game_id: game_id.clone(),
+                            seed,
+                            started_at: now,
                         })
                         .send_to(betting_chain);
                 }
@@ -187,255 +330,293 @@ impl Contract for GameContract {
                 OperationResult::GameAccepted { game_id }
             }
 
+            Operation::DeclineGame { game_id } => {
+                let Some(mut game) = self.state.get_game(&game_id).await else {
+                    return OperationResult::CancelRejected { reason: GameError::GameNotFound(game_id) };
+                };
+
+                // Only the invited player turns down a pending invite
+                let caller = self.caller();
+                if let Err(reason) = invite::decline(&mut game, &caller) {
+                    return OperationResult::CancelRejected { reason };
+                }
+                game.updated_at = self.runtime.system_time().micros();
+                self.state.update_game(game.clone()).await.expect("Failed to update game");
+
+                self.notify_opponent(&game, &caller, Message::GameDeclined {
+                    game_id: game_id.clone(),
+                });
+                self.handle_game_cancelled(&game).await;
+
+                OperationResult::GameDeclined { game_id }
+            }
+
+            Operation::CancelGame { game_id } => {
+                let Some(mut game) = self.state.get_game(&game_id).await else {
+                    return OperationResult::CancelRejected { reason: GameError::GameNotFound(game_id) };
+                };
+
+                // Withdraw or clear away an invite, or abort a game before both sides have moved
+                let caller = self.caller();
+                let now = self.runtime.system_time().micros();
+                if let Err(reason) = invite::cancel(&mut game, &caller, now) {
+                    return OperationResult::CancelRejected { reason };
+                }
+                game.updated_at = now;
+                self.state.update_game(game.clone()).await.expect("Failed to update game");
+
+                self.notify_opponent(&game, &caller, Message::GameCancelled {
+                    game_id: game_id.clone(),
+                    status: game.status,
+                });
+                self.handle_game_cancelled(&game).await;
+
+                OperationResult::GameCancelled { game_id }
+            }
+
             Operation::PlaceMove {
                 game_id,
                 from,
                 to,
                 promotion,
+            } => self.place_move(game_id, from, to, promotion, None).await,
+
+            Operation::PlaceMoveNotation {
+                game_id,
+                notation,
+                format,
             } => {
-                // Get game from local state
+                // Resolve the text against the legal moves, then play it like PlaceMove
+                let Some(game) = self.state.get_game(&game_id).await else {
+                    return OperationResult::MoveRejected { reason: GameError::GameNotFound(game_id) };
+                };
+
+                // A move out of turn or on a finished game fails as such, not as bad notation
+                let position = Self::load_position(&game);
+                if let Err(reason) = self.check_mover(&game, &position) {
+                    return OperationResult::MoveRejected { reason };
+                }
+                let resolved = match format {
+                    MoveNotation::San => position.parse_san(&notation),
+                    MoveNotation::Uci => position.parse_uci(&notation),
+                };
+                let mv = match resolved {
+                    Ok(mv) => mv,
+                    Err(e) => return OperationResult::MoveRejected { reason: GameError::IllegalMove(e) },
+                };
+
+                self.place_move(game_id, mv.from_square(), mv.to_square(), mv.promotion_letter(), mv.drop_letter())
+                    .await
+            }
+
+            Operation::ResignGame { game_id } => {
                 let game_result = self.state.get_game(&game_id).await;
 
                 let mut game = match game_result {
                     Some(g) => g,
                     None => {
-                        return OperationResult::MoveAccepted; // Silently fail - game not found
+                        return OperationResult::ResignRejected { reason: GameError::GameNotFound(game_id) };
                     }
                 };
 
-                // Verify game is active
-                if game.status != GameStatus::Active {
-                    // Game not active - could be pending (needs accept) or finished
-                    return OperationResult::MoveAccepted; // Return success to avoid frontend errors
+                let caller = self.caller();
+                if let Err(reason) = resign::resign(&mut game, &caller) {
+                    return OperationResult::ResignRejected { reason };
                 }
+                game.updated_at = self.runtime.system_time().micros();
 
-                // Determine whose turn it is (even move_count = white's turn, odd = black's turn)
-                let current_player = if game.move_count % 2 == 0 {
-                    game.white_player.clone()
-                } else {
-                    game.black_player.clone()
-                };
-
-                // Verify it's the caller's turn
-                let caller_chain = self.runtime.chain_id().to_string();
-                if caller_chain != current_player {
-                    // Not this player's turn - silently ignore
-                    return OperationResult::MoveAccepted;
-                }
-
-                // Create the move
-                let chess_move = ChessMove {
-                    from: from.clone(),
-                    to: to.clone(),
-                    promotion: None,
-                    piece: self.get_piece_at(&game.current_fen, &from),
-                    san: self.to_san(&game.current_fen, &from, &to),
-                    timestamp: self.runtime.system_time().micros(),
-                };
-
-                // Apply the move (simplified - in production, use chess.js or full validation)
-                let new_fen = self.apply_move(&game.current_fen, &chess_move, promotion.as_deref()).expect("Failed to apply move");
-
-                // Check for game end conditions
-                let (is_checkmate, is_stalemate) = self.check_game_end(&new_fen);
+                self.state.update_game(game.clone()).await.expect("Failed to update game");
 
-                if is_checkmate {
-                    game.status = GameStatus::Finished;
-                    game.result = Some(if game.move_count % 2 == 0 {
-                        GameResult::WhiteWins
-                    } else {
-                        GameResult::BlackWins
-                    });
-                } else if is_stalemate {
-                    game.status = GameStatus::Finished;
-                    game.result = Some(GameResult::Draw);
-                }
+                // Close the opponent's copy too, so it can't claim the resigner's clock afterwards
+                self.announce_finish(&game, &caller);
 
-                // Update game state
-                game.current_fen = new_fen.clone();
-                game.move_count += 1;
-                game.updated_at = self.runtime.system_time().micros();
-
-                // CRITICAL FIX: Update game.moves Vec for GraphQL queries
-                game.moves.push(chess_move.clone());
+                OperationResult::GameResigned { game_id }
+            }
 
-                self.state.update_game(game.clone()).await.expect("Failed to update game");
-                self.state.update_position(&game_id, new_fen.clone()).await.expect("Failed to update position");
-                self.state.add_move(&game_id, chess_move.clone()).await.expect("Failed to add move");
+            Operation::OfferDraw { game_id } => {
+                let game_result = self.state.get_game(&game_id).await;
 
-                // Notify opponent
-                let opponent = if current_player == game.white_player {
-                    game.black_player.clone()
-                } else {
-                    game.white_player.clone()
+                let mut game = match game_result {
+                    Some(g) => g,
+                    None => {
+                        return OperationResult::DrawRejected { reason: GameError::GameNotFound(game_id) };
+                    }
                 };
-                let opponent_chain = ChainId::from_str(&opponent).expect("Invalid opponent ChainId");
-
-                self.runtime
-                    .prepare_message(Message::OpponentMove {
-                        game_id: game_id.clone(),
-                        chess_move: chess_move.clone(),
-                    })
-                    .with_authentication()
-                    .with_tracking()
-                    .send_to(opponent_chain);
 
-                // Notify betting chain of position update
-                if let Some(betting_chain) = *self.state.betting_chain.get() {
-                    self.runtime
-                        .prepare_message(Message::PositionUpdated {
-                            game_id: game_id.clone(),
-                            fen: new_fen.clone(),
-                            move_count: game.move_count,
-                        })
-                        .send_to(betting_chain);
+                // Only one offer may be pending, from a player of an active game
+                let caller = self.caller();
+                if let Err(reason) = draw::offer(&mut game, &caller) {
+                    return OperationResult::DrawRejected { reason };
                 }
+                let ply = game.move_count;
+                self.state.update_game(game.clone()).await.expect("Failed to update game");
 
-                // Emit move event
-                self.runtime.emit(STREAM_NAME.into(), &GameEvent::MoveMade {
+                // Send draw offer to opponent
+                self.notify_opponent(&game, &caller, Message::DrawOffer {
                     game_id: game_id.clone(),
-                    chess_move: chess_move.clone(),
-                    new_fen: new_fen.clone(),
+                    ply,
                 });
 
-                // If game finished, notify all stakeholders
-                if game.status == GameStatus::Finished {
-                    self.handle_game_end(&game);
-                    // Emit game finished event
-                    if let Some(result) = game.result.clone() {
-                        self.runtime.emit(STREAM_NAME.into(), &GameEvent::GameFinished {
-                            game_id: game_id.clone(),
-                            result,
-                        });
-                    }
-                }
-
-                OperationResult::MoveAccepted
+                OperationResult::DrawOffered
             }
 
-            Operation::ResignGame { game_id } => {
+            Operation::AcceptDraw { game_id } => {
                 let game_result = self.state.get_game(&game_id).await;
 
                 let mut game = match game_result {
                     Some(g) => g,
                     None => {
-                        return OperationResult::GameResigned { game_id: format!("ERROR: Game {} not found", game_id) };
+                        return OperationResult::DrawRejected { reason: GameError::GameNotFound(game_id) };
                     }
                 };
 
-                // Verify game is active
-                if game.status != GameStatus::Active {
-                    return OperationResult::GameResigned { game_id: format!("ERROR: Game {} is not active", game_id) };
+                // Only a live offer from the opponent can be accepted
+                let caller = self.caller();
+                if let Err(reason) = draw::accept(&mut game, &caller) {
+                    return OperationResult::DrawRejected { reason };
                 }
+                game.updated_at = self.runtime.system_time().micros();
 
-                // Verify caller is a player
-                let caller = self.runtime.chain_id().to_string();
-                let is_white = caller == game.white_player;
-                let is_black = caller == game.black_player;
-
-                if !is_white && !is_black {
-                    return OperationResult::GameResigned { game_id: format!("ERROR: Not a player in game {}", game_id) };
-                }
+                self.state.update_game(game.clone()).await.expect("Failed to update game");
 
-                // Update game result
-                game.status = GameStatus::Finished;
-                game.result = Some(if is_white {
-                    GameResult::BlackWins
-                } else {
-                    GameResult::WhiteWins
+                // Notify opponent
+                self.notify_opponent(&game, &caller, Message::DrawAccepted {
+                    game_id: game_id.clone(),
                 });
 
-                self.state.update_game(game.clone()).await.expect("Failed to update game");
-
                 // Handle game end
                 self.handle_game_end(&game);
 
-                OperationResult::GameResigned { game_id }
+                OperationResult::DrawAccepted
             }
 
-            Operation::OfferDraw { game_id } => {
+            Operation::DeclineDraw { game_id } => {
                 let game_result = self.state.get_game(&game_id).await;
 
-                let game = match game_result {
+                let mut game = match game_result {
                     Some(g) => g,
                     None => {
-                        return OperationResult::DrawOffered;
+                        return OperationResult::DrawRejected { reason: GameError::GameNotFound(game_id) };
                     }
                 };
 
-                // Verify game is active
-                if game.status != GameStatus::Active {
-                    return OperationResult::DrawOffered;
+                let caller = self.caller();
+                if let Err(reason) = draw::decline(&mut game, &caller) {
+                    return OperationResult::DrawRejected { reason };
                 }
+                self.state.update_game(game.clone()).await.expect("Failed to update game");
 
-                // Verify caller is a player and get opponent
-                let caller = self.runtime.chain_id().to_string();
-                let opponent = if caller == game.white_player {
-                    game.black_player.clone()
-                } else if caller == game.black_player {
-                    game.white_player.clone()
-                } else {
-                    return OperationResult::DrawOffered;
-                };
-                let opponent_chain = ChainId::from_str(&opponent).expect("Invalid opponent ChainId");
-
-                // Send draw offer to opponent
-                self.runtime
-                    .prepare_message(Message::DrawOffer {
-                        game_id: game_id.clone(),
-                    })
-                    .with_authentication()
-                    .send_to(opponent_chain);
+                self.notify_opponent(&game, &caller, Message::DrawDeclined {
+                    game_id: game_id.clone(),
+                });
 
-                OperationResult::DrawOffered
+                OperationResult::DrawDeclined
             }
 
-            Operation::AcceptDraw { game_id } => {
+            Operation::ClaimDraw { game_id } => {
                 let game_result = self.state.get_game(&game_id).await;
 
                 let mut game = match game_result {
                     Some(g) => g,
                     None => {
-                        return OperationResult::DrawAccepted;
+                        return OperationResult::DrawRejected { reason: GameError::GameNotFound(game_id) };
                     }
                 };
 
-                // Verify game is active
-                if game.status != GameStatus::Active {
-                    return OperationResult::DrawAccepted;
-                }
-
-                // Update game result
-                game.status = GameStatus::Finished;
-                game.result = Some(GameResult::Draw);
+                // Only threefold repetition or the fifty-move rule can be claimed, by the side to move
+                let caller = self.caller();
+                let history = self.state.get_position_history(&game_id).await.expect("Failed to get position history");
+                let termination = match draw::claim(&mut game, &caller, &Self::load_position(&game), &history) {
+                    Ok(termination) => termination,
+                    Err(reason) => return OperationResult::DrawRejected { reason },
+                };
+                game.updated_at = self.runtime.system_time().micros();
 
                 self.state.update_game(game.clone()).await.expect("Failed to update game");
 
                 // Notify opponent
-                let opponent = if self.runtime.chain_id().to_string() == game.white_player {
-                    game.black_player.clone()
-                } else {
-                    game.white_player.clone()
+                self.notify_opponent(&game, &caller, Message::DrawClaimed {
+                    game_id: game_id.clone(),
+                    termination,
+                });
+
+                // Handle game end
+                self.handle_game_end(&game);
+
+                OperationResult::DrawClaimed
+            }
+
+            Operation::PlaceDrop {
+                game_id,
+                piece,
+                square,
+            } => self.place_move(game_id, String::new(), square, None, Some(piece)).await,
+
+            Operation::LinkBughouse {
+                game_id,
+                partner_game_id,
+                partner_white,
+                partner_black,
+            } => {
+                // Either player of a Bughouse board names the board its captures feed
+                let Some(mut game) = self.state.get_game(&game_id).await else {
+                    return OperationResult::LinkRejected { reason: GameError::GameNotFound(game_id) };
+                };
+                let caller = self.caller();
+                if caller != game.white_player && caller != game.black_player {
+                    return OperationResult::LinkRejected { reason: GameError::NotAPlayer };
+                }
+                if game.variant != Variant::Bughouse {
+                    return OperationResult::LinkRejected { reason: GameError::NotBughouse };
+                }
+
+                let partner = PartnerBoard {
+                    game_id: partner_game_id,
+                    white_player: partner_white.to_string(),
+                    black_player: partner_black.to_string(),
                 };
-                let opponent_chain = ChainId::from_str(&opponent).expect("Invalid opponent ChainId");
+                game.bughouse_partner = Some(partner.clone());
+                self.state.update_game(game.clone()).await.expect("Failed to update game");
 
+                // Both copies of the board must know where captures go
+                let opponent = if caller == game.white_player { &game.black_player } else { &game.white_player };
+                let opponent_chain = ChainId::from_str(opponent).expect("Invalid opponent ChainId");
                 self.runtime
-                    .prepare_message(Message::DrawAccepted {
-                        game_id: game_id.clone(),
-                    })
+                    .prepare_message(Message::BughouseLinked { game_id: game_id.clone(), partner })
                     .with_authentication()
+                    .with_tracking()
                     .send_to(opponent_chain);
 
-                // Handle game end
-                self.handle_game_end(&game);
-
-                OperationResult::DrawAccepted
+                OperationResult::BughouseLinked { game_id }
             }
 
             Operation::TimeoutGame { game_id } => {
-                // Timeout logic to be implemented in future version with time tracking
-                // For now, this operation is a placeholder for manual timeout calls
-                OperationResult::Timeout { game_id }
+                let game = match self.state.get_game(&game_id).await {
+                    Some(g) => g,
+                    None => {
+                        return OperationResult::TimeoutRejected { reason: GameError::GameNotFound(game_id) };
+                    }
+                };
+
+                if game.status != GameStatus::Active {
+                    return OperationResult::TimeoutRejected { reason: GameError::GameNotActive };
+                }
+
+                let caller = self.caller();
+                let claimant = if caller == game.white_player {
+                    Color::White
+                } else if caller == game.black_player {
+                    Color::Black
+                } else {
+                    return OperationResult::TimeoutRejected { reason: GameError::NotAPlayer };
+                };
+
+                let position = Self::load_position(&game);
+                let now = self.runtime.system_time().micros();
+                match clock::claim_timeout(&game, claimant, position.active_color, now) {
+                    Ok(flagged) => self.finish_on_time(game, &position, flagged).await,
+                    Err(reason) => OperationResult::TimeoutRejected { reason },
+                }
             }
 
             Operation::SetBettingChain { betting_chain } => {
@@ -445,6 +626,12 @@ impl Contract for GameContract {
                 OperationResult::ConfigUpdated
             }
 
+            Operation::SetBettingApp { betting_app } => {
+                // Set on the betting chain, where refunds call into the betting application
+                self.state.betting_app.set(Some(betting_app));
+                OperationResult::ConfigUpdated
+            }
+
             Operation::SetHubChain { hub_chain } => {
                 // Note: Admin authorization check should be added before production deployment
                 // Current implementation allows any chain to set hub chain (acceptable for testnet)
@@ -460,85 +647,636 @@ impl Contract for GameContract {
     ) {
         // Handle incoming cross-chain messages
         match message {
-            Message::GameInvite { game_id } => {
+            Message::GameInvite { identity, time_control, fen, chess960, variant, seed, game_chain, created_at } => {
                 // Game invitation received - create a copy of the game on this chain
                 // The game was created by white player, now black player receives the invite
                 let white_player = self.runtime.message_origin_chain_id().expect("No message origin");
+                let game_id = identity.id();
+                if identity.creator != white_player || self.state.get_game(&game_id).await.is_some() {
+                    return; // Only the creator invites to its games, and an ID is never reused
+                }
                 let timestamp = self.runtime.system_time().micros();
 
                 let game = ChessGame {
                     id: game_id.clone(),
                     white_player: white_player.to_string(),
                     black_player: self.runtime.chain_id().to_string(),
-                    fen: STARTING_FEN.to_string(),
-                    current_fen: STARTING_FEN.to_string(),
+                    fen: fen.clone(),
+                    current_fen: fen.clone(),
+                    zobrist_key: format!("{:016x}", self.zobrist_key(&fen, variant)),
+                    desynced: false,
                     moves: Vec::new(),
                     move_count: 0,
                     status: GameStatus::Pending,
                     result: None,
-                    created_at: timestamp,
+                    termination: None,
+                    draw_offer: None,
+                    time_control,
+                    white_time_left: 0,
+                    black_time_left: 0,
+                    clock_started_at: 0,
+                    chess960,
+                    variant,
+                    bughouse_partner: None,
+                    game_chain: game_chain.map(|chain| chain.to_string()),
+                    // The creator's time, so both chains agree on when the invite expires
+                    created_at,
                     updated_at: timestamp,
                 };
+                if let Some(game_chain) = game_chain {
+                    self.subscribe_to_game_chain(game_chain);
+                }
 
-                // Store the game on this chain
+                // Store the game on this chain, keeping the creator's seed share for acceptance
                 self.state.update_game(game).await.expect("Failed to store invited game");
-                self.state.update_position(&game_id, STARTING_FEN.to_string()).await.expect("Failed to store position");
+                if let Some(seed) = seed {
+                    self.state.chess960_seeds.insert(&game_id, seed).expect("Failed to store Chess960 seed");
+                }
+                self.state.add_position_key(&game_id, self.zobrist_key(&fen, variant)).await.expect("Failed to record position");
+                self.state.update_position(&game_id, fen).await.expect("Failed to store position");
+            }
+            Message::HostGame { game, seed, betting_chain, hub_chain } => {
+                // Only the creator can place its game on this chain
+                let origin = self.runtime.message_origin_chain_id().expect("No message origin");
+                if origin.to_string() != game.white_player
+                    || game.game_chain != Some(self.runtime.chain_id().to_string())
+                    || self.state.get_game(&game.id).await.is_some()
+                {
+                    return;
+                }
+
+                // A dedicated game chain reports to the creator's betting and hub chains
+                if self.state.betting_chain.get().is_none() {
+                    self.state.betting_chain.set(betting_chain);
+                }
+                if self.state.hub_chain.get().is_none() {
+                    self.state.hub_chain.set(hub_chain);
+                }
+
+                let game_id = game.id.clone();
+                let key = self.zobrist_key(&game.fen, game.variant);
+                if let Some(seed) = seed {
+                    self.state.chess960_seeds.insert(&game_id, seed).expect("Failed to store Chess960 seed");
+                }
+                self.state.add_position_key(&game_id, key).await.expect("Failed to record position");
+                self.state.update_position(&game_id, game.fen.clone()).await.expect("Failed to store position");
+                self.state.update_game(game.clone()).await.expect("Failed to store hosted game");
+                self.runtime.emit(STREAM_NAME.into(), &GameEvent::GameUpdated { game });
+            }
+            Message::HostedOperation { operation } => {
+                // A player acting on a game this chain hosts
+                let origin = self.runtime.message_origin_chain_id().expect("No message origin");
+                let Some(game_id) = operation.game_id().map(str::to_string) else {
+                    return;
+                };
+
+                // Operations that arrive before HostGame, or for games hosted elsewhere, bounce back
+                let rejection = if self.hosting_chain(&game_id).await != Some(self.runtime.chain_id()) {
+                    Some(GameError::GameNotFound(game_id.clone()))
+                } else {
+                    self.acting_for = Some(origin);
+                    let result = self.execute_operation(operation).await;
+                    self.acting_for = None;
+                    result.rejection().cloned()
+                };
+                if let Some(reason) = rejection {
+                    self.runtime
+                        .prepare_message(Message::HostedOperationRejected { game_id: game_id.clone(), reason })
+                        .with_authentication()
+                        .with_tracking()
+                        .send_to(origin);
+                }
+
+                // Publish the canonical copy for both players' mirrors
+                if let Some(game) = self.state.get_game(&game_id).await {
+                    self.runtime.emit(STREAM_NAME.into(), &GameEvent::GameUpdated { game });
+                }
+            }
+            Message::HostedOperationRejected { game_id, reason } => {
+                // Keep the game chain's reason on our mirror for the player to see
+                let origin = self.runtime.message_origin_chain_id().expect("No message origin");
+                if self.hosting_chain(&game_id).await == Some(origin) {
+                    self.state.hosted_rejections.insert(&game_id, reason).expect("Failed to record rejection");
+                }
             }
-            Message::OpponentMove { game_id, chess_move } => {
+            Message::OpponentMove { game_id, chess_move, zobrist_key, time_left } => {
                 // Opponent made a move - update our local game state
-                if let Some(mut game) = self.state.get_game(&game_id).await {
-                    // Apply the move to our local state
-                    let new_fen = self.apply_move(&game.current_fen, &chess_move, None)
-                        .unwrap_or_else(|_| game.current_fen.clone());
+                let Some(mut game) = self.state.get_game(&game_id).await else {
+                    return;
+                };
+                let origin = self.runtime.message_origin_chain_id().expect("No message origin").to_string();
+                if origin != game.white_player && origin != game.black_player {
+                    return; // Only the players of this game send its moves
+                }
+                if game.status != GameStatus::Active {
+                    return; // The game already ended here, e.g. on a timeout claim that crossed this move
+                }
 
-                    game.current_fen = new_fen.clone();
-                    game.move_count += 1;
-                    game.updated_at = self.runtime.system_time().micros();
+                // The sender must be the side to move on our copy, and the move must be
+                // legal here and reach the position the sender reached
+                let mut position = Self::load_position(&game);
+                let replayed = match sync::check_turn(&game, &position, &origin) {
+                    Err(error) => Err(error),
+                    Ok(()) => match chess_move.drop.as_deref() {
+                        Some(piece) => self.replay_drop(&game, &mut position, piece, &chess_move.to).await,
+                        None => position.apply_move(&chess_move.from, &chess_move.to, chess_move.promotion.as_deref()),
+                    },
+                };
+                if replayed.and_then(|()| sync::check_key(&position, zobrist_key)).is_err() {
+                    // Keep our copy as it was and fetch the reference copy instead
+                    self.resync(game).await;
+                    return;
+                }
+
+                // Run the mover's clock ourselves rather than trusting its report
+                let mover = position.active_color.opposite();
+                if clock::is_timed(&game) && clock::receive_move(&mut game, mover, time_left, chess_move.timestamp).is_err() {
+                    self.resync(game).await;
+                    return;
+                }
+                let new_fen = position.to_fen();
+                let local_key = position.zobrist_key();
 
-                    // CRITICAL FIX: Update game.moves Vec for GraphQL queries
-                    game.moves.push(chess_move.clone());
+                let history = self.state
+                    .add_position_key(&game_id, local_key)
+                    .await.expect("Failed to record position");
 
-                    self.state.update_game(game).await.expect("Failed to update game");
-                    self.state.update_position(&game_id, new_fen).await.expect("Failed to update position");
-                    self.state.add_move(&game_id, chess_move).await.expect("Failed to add move");
+                // Mirror the mover's end-of-game detection so both chains agree
+                if let Some(game_end) = position.check_game_end(&history) {
+                    game.status = GameStatus::Finished;
+                    game.result = Some(game_end.result());
+                    game.termination = Some(game_end.termination());
                 }
+
+                // The opponent moved instead of answering our draw offer
+                draw::expire_on_move(&mut game, &origin);
+
+                game.current_fen = new_fen.clone();
+                game.zobrist_key = format!("{:016x}", local_key);
+                game.move_count += 1;
+                game.updated_at = self.runtime.system_time().micros();
+
+                // CRITICAL FIX: Update game.moves Vec for GraphQL queries
+                game.moves.push(chess_move.clone());
+
+                self.state.update_game(game).await.expect("Failed to update game");
+                self.state.update_position(&game_id, new_fen).await.expect("Failed to update position");
+                self.state.add_move(&game_id, chess_move).await.expect("Failed to add move");
             }
-            Message::DrawOffer { game_id: _ } => {
-                // Draw offer received - player can accept via AcceptDraw
+            Message::RequestResync { game_id } => {
+                // Only the reference copy answers, and only to a player of the game
+                let origin = self.runtime.message_origin_chain_id().expect("No message origin");
+                let Some(game) = self.state.get_game(&game_id).await else {
+                    return;
+                };
+                if !sync::answers_request(&game, &self.runtime.chain_id().to_string(), &origin.to_string()) {
+                    return;
+                }
+                self.send_resync(&game, origin).await;
+            }
+            Message::Resync { game, position_history } => {
+                // Replace our copy wholesale with the reference chain's
+                let origin = self.runtime.message_origin_chain_id().expect("No message origin");
+                let Some(local) = self.state.get_game(&game.id).await else {
+                    return;
+                };
+                if !sync::accepts_resync(&local, &game, &origin.to_string()) {
+                    return;
+                }
+                self.state.restore_game(game, position_history).await.expect("Failed to restore game");
+            }
+            Message::DrawOffer { game_id, ply } => {
+                // Draw offer received - player can accept via AcceptDraw or decline via DeclineDraw
+                let origin = self.runtime.message_origin_chain_id().expect("No message origin").to_string();
+                if let Some(mut game) = self.state.get_game(&game_id).await {
+                    if draw::receive(&mut game, &origin, ply).is_ok() {
+                        self.state.update_game(game).await.expect("Failed to update game");
+                    }
+                }
             }
             Message::DrawAccepted { game_id } => {
-                // Draw accepted - update game status
+                // Draw accepted - only our own live offer ends the game
+                let origin = self.runtime.message_origin_chain_id().expect("No message origin").to_string();
                 if let Some(mut game) = self.state.get_game(&game_id).await {
-                    game.status = GameStatus::Finished;
-                    game.result = Some(GameResult::Draw);
-                    self.state.update_game(game).await.expect("Failed to update game");
+                    if draw::accept(&mut game, &origin).is_ok() {
+                        game.updated_at = self.runtime.system_time().micros();
+                        self.state.update_game(game).await.expect("Failed to update game");
+                    }
                 }
             }
-            Message::GameStarted { game_id } => {
-                // Game was accepted - update status
+            Message::DrawDeclined { game_id } => {
+                let origin = self.runtime.message_origin_chain_id().expect("No message origin").to_string();
                 if let Some(mut game) = self.state.get_game(&game_id).await {
+                    if draw::decline(&mut game, &origin).is_ok() {
+                        self.state.update_game(game).await.expect("Failed to update game");
+                    }
+                }
+            }
+            Message::DrawClaimed { game_id, termination } => {
+                // Opponent claimed a threefold repetition or fifty-move draw
+                let origin = self.runtime.message_origin_chain_id().expect("No message origin").to_string();
+                let Some(mut game) = self.state.get_game(&game_id).await else {
+                    return;
+                };
+                if (origin != game.white_player && origin != game.black_player) || game.status != GameStatus::Active {
+                    return;
+                }
+
+                // The claim must hold on our copy too, with the claimant to move; otherwise the copies have diverged
+                let history = self.state.get_position_history(&game_id).await.expect("Failed to get position history");
+                let position = Self::load_position(&game);
+                let claim = position.claimable_draw(&history);
+                if sync::check_turn(&game, &position, &origin).is_err() || claim.map(|claim| claim.termination()) != Some(termination) {
+                    self.resync(game).await;
+                    return;
+                }
+
+                game.status = GameStatus::Finished;
+                game.result = Some(GameResult::Draw);
+                game.termination = Some(termination);
+                game.updated_at = self.runtime.system_time().micros();
+                self.state.update_game(game).await.expect("Failed to update game");
+            }
+            Message::GameStarted { game_id, seed, started_at } => {
+                // Game was accepted - update status and draw the same Chess960 start as the acceptor
+                let origin = self.runtime.message_origin_chain_id().expect("No message origin").to_string();
+                if let Some(mut game) = self.state.get_game(&game_id).await {
+                    // An acceptance that crossed our CancelGame finds the invite closed; our
+                    // GameCancelled closes the invitee's copy in turn
+                    if origin != game.black_player || game.status != GameStatus::Pending {
+                        return;
+                    }
+                    let creator_seed = self.state.take_chess960_seed(&game_id).await.expect("Failed to get Chess960 seed");
+                    if let (Some(creator_seed), Some(seed)) = (creator_seed, seed) {
+                        self.set_chess960_start(&mut game, creator_seed, seed).await;
+                    }
+                    // Both chains run the clock from the acceptor's time so they agree on flag fall
                     game.status = GameStatus::Active;
+                    game.updated_at = started_at;
+                    clock::start(&mut game, started_at);
                     self.state.update_game(game).await.expect("Failed to update game");
                 }
             }
-            Message::GameFinished { game_id, result } => {
-                // Game finished - update status
+            Message::GameDeclined { game_id } => {
+                // Our invite was turned down
+                let origin = self.runtime.message_origin_chain_id().expect("No message origin").to_string();
+                if let Some(mut game) = self.state.get_game(&game_id).await {
+                    if invite::decline(&mut game, &origin).is_ok() {
+                        game.updated_at = self.runtime.system_time().micros();
+                        self.state.update_game(game).await.expect("Failed to update game");
+                        self.state.take_chess960_seed(&game_id).await.expect("Failed to drop Chess960 seed");
+                    }
+                }
+            }
+            Message::GameCancelled { game_id, status } => {
+                // The opponent withdrew the invite or aborted the game on its chain
+                let origin = self.runtime.message_origin_chain_id().expect("No message origin").to_string();
                 if let Some(mut game) = self.state.get_game(&game_id).await {
+                    let is_player = origin == game.white_player || origin == game.black_player;
+                    let still_open = matches!(game.status, GameStatus::Pending | GameStatus::Active);
+                    if is_player && still_open && matches!(status, GameStatus::Cancelled | GameStatus::Aborted) {
+                        game.status = status;
+                        game.draw_offer = None;
+                        game.updated_at = self.runtime.system_time().micros();
+                        self.state.update_game(game).await.expect("Failed to update game");
+                        self.state.take_chess960_seed(&game_id).await.expect("Failed to drop Chess960 seed");
+                    }
+                }
+            }
+            Message::OpenBetting { game_id, black_player, game_chain } => {
+                // Received on the betting chain from the creator, whose chain prefixes the game ID
+                let origin = self.runtime.message_origin_chain_id().expect("No message origin");
+                if !GameIdentity::is_created_by(&game_id, origin) {
+                    return;
+                }
+                let chains = [Some(origin), Some(black_player), game_chain].into_iter().flatten().collect();
+                self.state.refund_chains.insert(&game_id, chains).expect("Failed to record refund chains");
+            }
+            Message::RefundBets { game_id } => {
+                // Received on the betting chain: the betting application returns the stakes,
+                // but only a player or the game chain may call the game off
+                let origin = self.runtime.message_origin_chain_id().expect("No message origin");
+                let chains = self.state.refund_chains.get(&game_id).await.expect("Failed to get refund chains");
+                if !chains.is_some_and(|chains| chains.contains(&origin)) {
+                    return;
+                }
+                let Some(betting_app) = *self.state.betting_app.get() else {
+                    return;
+                };
+                self.runtime.call_application(
+                    true,
+                    betting_app.with_abi::<BettingAbi>(),
+                    &BettingOperation::RefundGame { game_id },
+                );
+            }
+            Message::BughouseLinked { game_id, partner } => {
+                // Our opponent linked this board to its Bughouse partner board
+                let origin = self.runtime.message_origin_chain_id().expect("No message origin").to_string();
+                if let Some(mut game) = self.state.get_game(&game_id).await {
+                    if game.variant == Variant::Bughouse && (origin == game.white_player || origin == game.black_player) {
+                        game.bughouse_partner = Some(partner);
+                        self.state.update_game(game).await.expect("Failed to update game");
+                    }
+                }
+            }
+            Message::PocketPiece { game_id, piece } => {
+                // A capture on the partner board passes the piece to our pocket
+                let origin = self.runtime.message_origin_chain_id().expect("No message origin").to_string();
+                if let Some(mut game) = self.state.get_game(&game_id).await {
+                    let from_partner = game
+                        .bughouse_partner
+                        .as_ref()
+                        .is_some_and(|partner| origin == partner.white_player || origin == partner.black_player);
+                    if !from_partner || game.status != GameStatus::Active {
+                        return;
+                    }
+                    // Already credited when the opponent dropped it before this message arrived
+                    if self.state.take_pocket_advance(&game_id, &piece).await.expect("Failed to settle pocket piece") {
+                        return;
+                    }
+
+                    let mut position = Self::load_position(&game);
+                    let Ok((received, color)) = Position::parse_pocket_letter(&piece) else {
+                        return;
+                    };
+                    position.add_to_pocket(color, received).expect("Bughouse game without pockets");
+
+                    // Pockets are part of the key, so the current position's history entry changes too
+                    sync::set_position(&mut game, &position);
+                    game.updated_at = self.runtime.system_time().micros();
+                    self.state
+                        .replace_position_key(&game_id, position.zobrist_key())
+                        .await.expect("Failed to record position");
+                    self.state.update_position(&game_id, game.current_fen.clone()).await.expect("Failed to update position");
+                    self.state.update_game(game).await.expect("Failed to update game");
+                }
+            }
+            Message::GameFinished { game_id, result, termination } => {
+                // The opponent or the game chain ended a game still running on this chain
+                let origin = self.runtime.message_origin_chain_id().expect("No message origin").to_string();
+                if let Some(mut game) = self.state.get_game(&game_id).await {
+                    let from_peer = origin == game.white_player
+                        || origin == game.black_player
+                        || game.game_chain.as_deref() == Some(origin.as_str());
+                    if !from_peer || game.status != GameStatus::Active {
+                        return;
+                    }
                     game.status = GameStatus::Finished;
                     game.result = Some(result);
+                    game.termination = termination;
+                    game.updated_at = self.runtime.system_time().micros();
                     self.state.update_game(game).await.expect("Failed to update game");
+                    self.runtime.emit(STREAM_NAME.into(), &GameEvent::GameFinished { game_id, result, termination });
                 }
             }
             _ => {} // Other messages handled by their respective chains
         }
     }
 
+    async fn process_streams(&mut self, updates: Vec<StreamUpdate>) {
+        // Refresh our mirrors of games hosted on the chains we follow
+        for update in updates {
+            for index in update.new_indices() {
+                let event = self.runtime.read_event(update.chain_id, STREAM_NAME.into(), index);
+                let GameEvent::GameUpdated { game } = event else {
+                    continue;
+                };
+                let Some(local) = self.state.get_game(&game.id).await else {
+                    continue;
+                };
+                if local.game_chain == Some(update.chain_id.to_string()) && game.game_chain == local.game_chain {
+                    self.state.mirror_game(game).await.expect("Failed to mirror game");
+                }
+            }
+        }
+    }
+
     async fn store(mut self) {
         self.state.save().await.expect("Failed to save state");
     }
 }
 
 impl GameContract {
+    /// Validate and play a move or pocket drop for the caller, then notify all stakeholders
+    async fn place_move(
+        &mut self,
+        game_id: String,
+        from: String,
+        to: String,
+        promotion: Option<String>,
+        drop: Option<String>,
+    ) -> OperationResult {
+        // Get game from local state
+        let game_result = self.state.get_game(&game_id).await;
+
+        let mut game = match game_result {
+            Some(g) => g,
+            None => {
+                return OperationResult::MoveRejected { reason: GameError::GameNotFound(game_id) };
+            }
+        };
+
+        // Parse the position once and reuse it for every step of the move
+        let mut position = Self::load_position(&game);
+
+        // Verify the game is active and it's the caller's turn
+        if let Err(reason) = self.check_mover(&game, &position) {
+            return OperationResult::MoveRejected { reason };
+        }
+        let caller_chain = self.caller();
+
+        // Stop the mover's clock; a move after the flag fell ends the game instead
+        let now = self.runtime.system_time().micros();
+        let mover = position.active_color;
+        if clock::is_timed(&game) && !clock::record_move(&mut game, mover, now) {
+            return self.finish_on_time(game, &position, mover).await;
+        }
+
+        // Resolve the move through the legal move generator
+        let promotion = promotion.map(|p| p.to_ascii_lowercase());
+        let drop = drop.map(|p| p.to_ascii_uppercase());
+        let resolved = match drop.as_deref() {
+            Some(piece) => position.find_legal_drop(piece, &to),
+            None => position.find_legal_move(&from, &to, promotion.as_deref()),
+        };
+        let mv = match resolved {
+            Ok(mv) => mv,
+            Err(e) => return OperationResult::MoveRejected { reason: GameError::IllegalMove(e) },
+        };
+
+        // Create the move, keeping the promotion or dropped piece so the opponent replays it
+        let chess_move = ChessMove {
+            from: mv.from_square(),
+            to: to.clone(),
+            piece: match mv.drop {
+                Some(piece) => Position::pocket_letter(piece, position.active_color),
+                None => position.get_piece_at(&from).unwrap_or_else(|| "?".to_string()),
+            },
+            san: position.move_to_san(&mv),
+            promotion,
+            timestamp: now,
+            drop,
+        };
+
+        // A Bughouse capture keeps its color and goes to the partner board
+        let pocket_piece = position
+            .pocket_capture(&mv)
+            .filter(|_| game.variant == Variant::Bughouse)
+            .map(|piece| Position::pocket_letter(piece, position.active_color.opposite()));
+
+        position.make_move(&mv);
+        let new_fen = position.to_fen();
+
+        // Record the position for repetition detection
+        let zobrist_key = position.zobrist_key();
+        let history = self.state
+            .add_position_key(&game_id, zobrist_key)
+            .await.expect("Failed to record position");
+
+        // Check for game end conditions
+        if let Some(game_end) = position.check_game_end(&history) {
+            game.status = GameStatus::Finished;
+            game.result = Some(game_end.result());
+            game.termination = Some(game_end.termination());
+        }
+
+        // Moving instead of answering declines the opponent's draw offer
+        draw::expire_on_move(&mut game, &caller_chain);
+
+        // Update game state
+        game.current_fen = new_fen.clone();
+        game.zobrist_key = format!("{:016x}", zobrist_key);
+        game.move_count += 1;
+        game.updated_at = now;
+
+        // CRITICAL FIX: Update game.moves Vec for GraphQL queries
+        game.moves.push(chess_move.clone());
+
+        self.state.update_game(game.clone()).await.expect("Failed to update game");
+        self.state.update_position(&game_id, new_fen.clone()).await.expect("Failed to update position");
+        self.state.add_move(&game_id, chess_move.clone()).await.expect("Failed to add move");
+
+        // Notify opponent
+        let time_left = match mover {
+            Color::White => game.white_time_left,
+            Color::Black => game.black_time_left,
+        };
+        self.notify_opponent(&game, &caller_chain, Message::OpponentMove {
+            game_id: game_id.clone(),
+            chess_move: chess_move.clone(),
+            zobrist_key: position.sync_key(),
+            time_left,
+        });
+
+        // Feed the captured piece to both chains of the partner board
+        if let (Some(piece), Some(partner)) = (pocket_piece, &game.bughouse_partner) {
+            for player in [&partner.white_player, &partner.black_player] {
+                let partner_chain = ChainId::from_str(player).expect("Invalid partner ChainId");
+                self.runtime
+                    .prepare_message(Message::PocketPiece {
+                        game_id: partner.game_id.clone(),
+                        piece: piece.clone(),
+                    })
+                    .with_authentication()
+                    .with_tracking()
+                    .send_to(partner_chain);
+            }
+        }
+
+        // Notify betting chain of position update
+        if let Some(betting_chain) = *self.state.betting_chain.get() {
+            self.runtime
+                .prepare_message(Message::PositionUpdated {
+                    game_id: game_id.clone(),
+                    fen: new_fen.clone(),
+                    move_count: game.move_count,
+                })
+                .send_to(betting_chain);
+        }
+
+        // Emit move event
+        self.runtime.emit(STREAM_NAME.into(), &GameEvent::MoveMade {
+            game_id: game_id.clone(),
+            chess_move: chess_move.clone(),
+            new_fen: new_fen.clone(),
+        });
+
+        // If game finished, notify all stakeholders
+        if game.status == GameStatus::Finished {
+            self.handle_game_end(&game);
+            // Emit game finished event
+            if let Some(result) = game.result.clone() {
+                self.runtime.emit(STREAM_NAME.into(), &GameEvent::GameFinished {
+                    game_id: game_id.clone(),
+                    result,
+                    termination: game.termination,
+                });
+            }
+        }
+
+        OperationResult::MoveAccepted
+    }
+
+    /// Check `game` is active and the caller is the side to move in `position`
+    fn check_mover(&mut self, game: &ChessGame, position: &Position) -> Result<(), GameError> {
+        if game.status != GameStatus::Active {
+            // Game not active - could be pending (needs accept) or finished
+            return Err(GameError::GameNotActive);
+        }
+
+        // Whose turn it is comes from the position, which may start with black to move
+        let current_player = match position.active_color {
+            Color::White => &game.white_player,
+            Color::Black => &game.black_player,
+        };
+        let caller = self.caller();
+        if caller != *current_player {
+            if caller == game.white_player || caller == game.black_player {
+                return Err(GameError::NotYourTurn);
+            }
+            return Err(GameError::NotAPlayer);
+        }
+        Ok(())
+    }
+
+    /// End the game because `flagged` ran out of time
+    ///
+    /// Reached when the flagged side tries to move too late or when its
+    /// opponent claims the timeout.
+    async fn finish_on_time(&mut self, mut game: ChessGame, position: &Position, flagged: Color) -> OperationResult {
+        let (result, termination) = clock::flag_result(position, flagged);
+        game.status = GameStatus::Finished;
+        game.result = Some(result);
+        game.termination = Some(termination);
+        game.updated_at = self.runtime.system_time().micros();
+        self.state.update_game(game.clone()).await.expect("Failed to update game");
+
+        // Either side may be the one finishing it
+        let caller = self.caller();
+        self.announce_finish(&game, &caller);
+
+        OperationResult::Timeout { game_id: game.id }
+    }
+
+    /// Report a game `player` just finished to the other player's copy, the betting and hub chains and our followers
+    fn announce_finish(&mut self, game: &ChessGame, player: &str) {
+        let Some(result) = game.result else {
+            return;
+        };
+        self.notify_opponent(game, player, Message::GameFinished {
+            game_id: game.id.clone(),
+            result,
+            termination: game.termination,
+        });
+
+        self.handle_game_end(game);
+        self.runtime.emit(STREAM_NAME.into(), &GameEvent::GameFinished {
+            game_id: game.id.clone(),
+            result,
+            termination: game.termination,
+        });
+    }
+
     /// Handle game end - notify betting chain and hub chain
     fn handle_game_end(&mut self, game: &ChessGame) {
         // Notify betting chain for settlement
@@ -548,14 +1286,23 @@ impl GameContract {
                     .prepare_message(Message::GameFinished {
                         game_id: game.id.clone(),
                         result,
+                        termination: game.termination,
                     })
                     .send_to(betting_chain);
             }
         }
 
-        // Notify hub chain with winner
+        // Notify hub chain of the outcome, and of the winner for the leaderboard
         if let Some(hub_chain) = *self.state.hub_chain.get() {
             if let Some(result) = game.result {
+                self.runtime
+                    .prepare_message(Message::GameFinished {
+                        game_id: game.id.clone(),
+                        result,
+                        termination: game.termination,
+                    })
+                    .send_to(hub_chain);
+
                 let winner = match result {
                     GameResult::WhiteWins => ChainId::from_str(&game.white_player).expect("Invalid white player ChainId"),
                     GameResult::BlackWins => ChainId::from_str(&game.black_player).expect("Invalid black player ChainId"),
@@ -570,39 +1317,192 @@ impl GameContract {
         }
     }
 
-    /// Get piece at position from FEN
-    fn get_piece_at(&self, fen: &str, square: &str) -> String {
-        match Position::from_fen(fen) {
-            Ok(pos) => pos.get_piece_at(square).unwrap_or_else(|| "?".to_string()),
-            Err(_) => "?".to_string(),
+    /// Cancel `game_id` if it is an invite that outlived its TTL, refunding its bets
+    async fn close_expired_invite(&mut self, game_id: &str) -> bool {
+        let Some(mut game) = self.state.get_game(game_id).await else {
+            return false;
+        };
+        let now = self.runtime.system_time().micros();
+        if !invite::expired(&game, now) {
+            return false;
         }
+
+        game.status = GameStatus::Cancelled;
+        game.updated_at = now;
+        self.state.update_game(game.clone()).await.expect("Failed to update game");
+
+        let caller = self.caller();
+        self.notify_opponent(&game, &caller, Message::GameCancelled {
+            game_id: game.id.clone(),
+            status: game.status,
+        });
+        self.handle_game_cancelled(&game).await;
+        true
     }
 
-    /// Convert move to SAN notation
-    fn to_san(&self, fen: &str, from: &str, to: &str) -> String {
-        match Position::from_fen(fen) {
-            Ok(pos) => pos.to_san(from, to),
-            Err(_) => format!("{}{}", from, to),
+    /// Handle a game called off before it was played out - refund bets and drop its seed share
+    async fn handle_game_cancelled(&mut self, game: &ChessGame) {
+        self.state.take_chess960_seed(&game.id).await.expect("Failed to drop Chess960 seed");
+
+        // Bets on a game that never ran are returned rather than settled
+        if let Some(betting_chain) = *self.state.betting_chain.get() {
+            self.runtime
+                .prepare_message(Message::RefundBets { game_id: game.id.clone() })
+                .with_authentication()
+                .with_tracking()
+                .send_to(betting_chain);
         }
+
+        self.runtime.emit(STREAM_NAME.into(), &GameEvent::GameCancelled {
+            game_id: game.id.clone(),
+            status: game.status,
+        });
     }
 
-    /// Apply move to FEN position
-    fn apply_move(
-        &self,
-        fen: &str,
-        chess_move: &ChessMove,
-        promotion: Option<&str>,
-    ) -> Result<String, String> {
-        let mut pos = Position::from_fen(fen)?;
-        pos.apply_move(&chess_move.from, &chess_move.to, promotion)?;
-        Ok(pos.to_fen())
+    /// Replay the opponent's drop on our copy of the board
+    ///
+    /// In Bughouse the piece comes from the partner board, whose PocketPiece
+    /// message may reach this chain after the drop itself. The mover's chain
+    /// already had it, so it is credited here and the late message skipped.
+    async fn replay_drop(
+        &mut self,
+        game: &ChessGame,
+        position: &mut Position,
+        piece: &str,
+        square: &str,
+    ) -> Result<(), String> {
+        let (dropped, _) = Position::parse_pocket_letter(piece)?;
+        let color = position.active_color;
+        if game.variant == Variant::Bughouse && position.pocket_count(color, dropped) == 0 {
+            let mut credited = position.clone();
+            credited.add_to_pocket(color, dropped)?;
+            credited.apply_drop(piece, square)?;
+            self.state.add_pocket_advance(&game.id, &Position::pocket_letter(dropped, color)).await?;
+            *position = credited;
+            return Ok(());
+        }
+        position.apply_drop(piece, square)
     }
 
-    /// Check if position is checkmate or stalemate
-    fn check_game_end(&self, fen: &str) -> (bool, bool) {
-        match Position::from_fen(fen) {
-            Ok(pos) => pos.check_game_end(),
-            Err(_) => (false, false),
+    /// Chain of `player`'s opponent in `game`
+    fn opponent_chain(game: &ChessGame, player: &str) -> ChainId {
+        let opponent = if player == game.white_player { &game.black_player } else { &game.white_player };
+        ChainId::from_str(opponent).expect("Invalid opponent ChainId")
+    }
+
+    /// Chain holding the reference copy of a game: its game chain, or else its creator, who plays White
+    fn authoritative_chain(game: &ChessGame) -> ChainId {
+        ChainId::from_str(sync::authoritative(game)).expect("Invalid authoritative ChainId")
+    }
+
+    /// Chain an operation runs for: the forwarding player on a game chain, else this chain
+    fn caller(&mut self) -> String {
+        self.acting_for.unwrap_or_else(|| self.runtime.chain_id()).to_string()
+    }
+
+    /// Send `message` to the other player of `game`
+    ///
+    /// Hosted games skip this: both players follow the game chain's events instead.
+    fn notify_opponent(&mut self, game: &ChessGame, player: &str, message: Message) {
+        if game.game_chain.is_some() {
+            return;
+        }
+        let opponent_chain = Self::opponent_chain(game, player);
+        self.runtime
+            .prepare_message(message)
+            .with_authentication()
+            .with_tracking()
+            .send_to(opponent_chain);
+    }
+
+    /// Game chain hosting `game_id`, if the game is hosted
+    async fn hosting_chain(&self, game_id: &str) -> Option<ChainId> {
+        let game = self.state.get_game(game_id).await?;
+        game.game_chain.map(|chain| ChainId::from_str(&chain).expect("Invalid game ChainId"))
+    }
+
+    /// Follow a game chain's event stream to keep our mirrors of its games current
+    fn subscribe_to_game_chain(&mut self, game_chain: ChainId) {
+        let application_id = self.runtime.application_id().forget_abi();
+        self.runtime.subscribe_to_events(game_chain, application_id, STREAM_NAME.into());
+    }
+
+    /// Recover from a move that doesn't fit our copy of the game
+    ///
+    /// The authoritative chain pushes its copy to the opponent; the other
+    /// player marks its copy desynced and asks for the reference one.
+    async fn resync(&mut self, mut game: ChessGame) {
+        let authoritative = Self::authoritative_chain(&game);
+        if !sync::mark_desynced(&mut game, &self.runtime.chain_id().to_string()) {
+            let opponent_chain = Self::opponent_chain(&game, &game.white_player);
+            self.send_resync(&game, opponent_chain).await;
+            return;
         }
+
+        self.state.update_game(game.clone()).await.expect("Failed to update game");
+        self.runtime
+            .prepare_message(Message::RequestResync { game_id: game.id })
+            .with_authentication()
+            .with_tracking()
+            .send_to(authoritative);
+    }
+
+    /// Send our full copy of a game, with its position history, to `chain`
+    async fn send_resync(&mut self, game: &ChessGame, chain: ChainId) {
+        let position_history = self.state.get_position_history(&game.id).await.expect("Failed to get position history");
+        self.runtime
+            .prepare_message(Message::Resync { game: game.clone(), position_history })
+            .with_authentication()
+            .with_tracking()
+            .send_to(chain);
+    }
+
+    /// Current position of a game under its variant, with Chess960 castling when the game uses it
+    fn load_position(game: &ChessGame) -> Position {
+        let mut position = Position::from_fen_variant(&game.current_fen, game.variant).expect("Invalid stored position");
+        if game.chess960 {
+            position.enable_chess960();
+        }
+        position
+    }
+
+    /// Replace a pending game's start with the Chess960 position both seed shares select
+    ///
+    /// Both chains run this with the same two shares, so they agree on the
+    /// position. The creator chooses it: its share is its creation time and
+    /// the acceptor's is fixed by the game, so the creator can compute the
+    /// back rank before sending the invite, just as it could give any start
+    /// through `starting_position`. The acceptor cannot steer it. Making the
+    /// draw fair to both sides would take a commit/reveal round before play.
+    async fn set_chess960_start(&mut self, game: &mut ChessGame, creator_seed: u64, acceptor_seed: u64) {
+        // One splitmix64 round so nearby timestamps still spread over all 960 starts
+        let mut z = (creator_seed ^ acceptor_seed.rotate_left(32)).wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        let index = ((z ^ (z >> 31)) % 960) as u32;
+
+        let fen = Position::chess960_fen(index);
+        let key = self.zobrist_key(&fen, game.variant);
+        game.fen = fen.clone();
+        game.current_fen = fen.clone();
+        game.zobrist_key = format!("{:016x}", key);
+
+        self.state.update_position(&game.id, fen).await.expect("Failed to store Chess960 position");
+        self.state.reset_position_history(&game.id, key).await.expect("Failed to record Chess960 position");
+    }
+
+    /// Acceptor's seed share, fixed by the game ID and both players' chains rather than
+    /// picked when accepting
+    fn chess960_share(game: &ChessGame) -> u64 {
+        // FNV-1a
+        [&game.id, &game.white_player, &game.black_player]
+            .iter()
+            .flat_map(|text| text.bytes())
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3))
+    }
+
+    /// Zobrist key of a FEN position
+    fn zobrist_key(&self, fen: &str, variant: Variant) -> u64 {
+        Position::from_fen_variant(fen, variant).map(|pos| pos.zobrist_key()).unwrap_or_default()
     }
 }
@@ -0,0 +1,95 @@
+//! Draw offers
+//!
+//! A player may offer a draw at any point of an active game. The offer stays
+//! open until the opponent accepts or declines it, or moves instead, which
+//! declines it implicitly. Only one offer is pending at a time.
+
+use crate::chess_logic::{Color, Position};
+use abi::{ChessGame, DrawOffer, GameError, GameResult, GameStatus, Termination};
+
+/// Record a draw offer from `player` at the current ply
+pub fn offer(game: &mut ChessGame, player: &str) -> Result<(), GameError> {
+    check_player(game, player)?;
+    if game.draw_offer.is_some() {
+        return Err(GameError::DrawOfferPending);
+    }
+    game.draw_offer = Some(DrawOffer { offered_by: player.to_string(), ply: game.move_count });
+    Ok(())
+}
+
+/// Record the opponent's offer made at `ply` as it reaches this chain
+///
+/// An offer that crossed with a move of ours is already stale: that move
+/// declined it before we saw it.
+pub fn receive(game: &mut ChessGame, offered_by: &str, ply: u32) -> Result<(), GameError> {
+    check_player(game, offered_by)?;
+    if game.move_count > ply {
+        return Err(GameError::NoDrawOffer);
+    }
+    game.draw_offer = Some(DrawOffer { offered_by: offered_by.to_string(), ply });
+    Ok(())
+}
+
+/// Accept the opponent's live offer, ending the game as a draw by agreement
+pub fn accept(game: &mut ChessGame, player: &str) -> Result<(), GameError> {
+    take_opponent_offer(game, player)?;
+    game.status = GameStatus::Finished;
+    game.result = Some(GameResult::Draw);
+    game.termination = Some(Termination::Agreement);
+    Ok(())
+}
+
+/// Claim a threefold repetition or fifty-move draw for `player` in `position`
+///
+/// As under FIDE rules only the side to move may claim, before moving.
+/// Returns the termination that ended the game.
+pub fn claim(game: &mut ChessGame, player: &str, position: &Position, history: &[u64]) -> Result<Termination, GameError> {
+    check_player(game, player)?;
+    let to_move = match position.active_color {
+        Color::White => &game.white_player,
+        Color::Black => &game.black_player,
+    };
+    if player != to_move {
+        return Err(GameError::NotYourTurn);
+    }
+    let claim = position.claimable_draw(history).ok_or(GameError::NoClaimableDraw)?;
+    game.status = GameStatus::Finished;
+    game.result = Some(claim.result());
+    game.termination = Some(claim.termination());
+    game.draw_offer = None;
+    Ok(claim.termination())
+}
+
+/// Decline the opponent's live offer
+pub fn decline(game: &mut ChessGame, player: &str) -> Result<(), GameError> {
+    take_opponent_offer(game, player)
+}
+
+/// Drop a pending offer once the offerer's opponent moves instead of answering it
+pub fn expire_on_move(game: &mut ChessGame, mover: &str) {
+    if game.draw_offer.as_ref().is_some_and(|offer| offer.offered_by != mover) {
+        game.draw_offer = None;
+    }
+}
+
+fn check_player(game: &ChessGame, player: &str) -> Result<(), GameError> {
+    if game.status != GameStatus::Active {
+        return Err(GameError::GameNotActive);
+    }
+    if player != game.white_player && player != game.black_player {
+        return Err(GameError::NotAPlayer);
+    }
+    Ok(())
+}
+
+fn take_opponent_offer(game: &mut ChessGame, player: &str) -> Result<(), GameError> {
+    check_player(game, player)?;
+    match &game.draw_offer {
+        Some(offer) if offer.offered_by != player => {
+            game.draw_offer = None;
+            Ok(())
+        }
+        Some(_) => Err(GameError::OwnDrawOffer),
+        None => Err(GameError::NoDrawOffer),
+    }
+}
@@ -0,0 +1,52 @@
+//! Invites and early cancellation
+//!
+//! A created game stays `Pending` until the invited player accepts it. The
+//! invitee may decline it and the creator may withdraw it; once an invite
+//! has waited `INVITE_TTL` it can no longer be accepted and either player
+//! may clear it away. A started game can still be aborted until both
+//! players have moved.
+
+use abi::{ChessGame, GameError, GameStatus};
+
+/// How long an invite stays open, in microseconds
+pub const INVITE_TTL: u64 = 24 * 60 * 60 * 1_000_000;
+
+/// Whether `game` is an invite that has outlived `INVITE_TTL` at `now`
+pub fn expired(game: &ChessGame, now: u64) -> bool {
+    game.status == GameStatus::Pending && now.saturating_sub(game.created_at) >= INVITE_TTL
+}
+
+/// Whether `game` was called off before it was played out
+pub fn is_cancelled(game: &ChessGame) -> bool {
+    matches!(game.status, GameStatus::Cancelled | GameStatus::Aborted)
+}
+
+/// Turn down an invite as the invited player
+pub fn decline(game: &mut ChessGame, player: &str) -> Result<(), GameError> {
+    if player != game.black_player {
+        return Err(GameError::NotInvited);
+    }
+    if game.status != GameStatus::Pending {
+        return Err(GameError::GameNotPending);
+    }
+    game.status = GameStatus::Cancelled;
+    Ok(())
+}
+
+/// Call off a game for `player` at `now`
+///
+/// The creator may withdraw its invite at any time and either player may
+/// drop an expired one, leaving the game `Cancelled`. An active game is
+/// `Aborted` while one side has yet to make its first move.
+pub fn cancel(game: &mut ChessGame, player: &str, now: u64) -> Result<(), GameError> {
+    if player != game.white_player && player != game.black_player {
+        return Err(GameError::NotAPlayer);
+    }
+    game.status = match game.status {
+        GameStatus::Pending if player == game.white_player || expired(game, now) => GameStatus::Cancelled,
+        GameStatus::Active if game.move_count < 2 => GameStatus::Aborted,
+        _ => return Err(GameError::CannotCancel),
+    };
+    game.draw_offer = None;
+    Ok(())
+}
@@ -1,5 +1,5 @@
-use abi::{ChessMove, GameResult};
-use linera_sdk::linera_base_types::{ChainId, ContractAbi, ServiceAbi};
+use abi::{ChessGame, ChessMove, GameError, GameResult, GameStatus, MoveNotation, PartnerBoard, Termination, TimeControl, TimeControlPreset, Variant};
+use linera_sdk::linera_base_types::{ApplicationId, ChainId, ContractAbi, ServiceAbi};
 use linera_sdk::graphql::GraphQLMutationRoot;
 use serde::{Deserialize, Serialize};
 
@@ -15,28 +15,68 @@ impl ServiceAbi for GameAbi {
     type QueryResponse = async_graphql::Response;
 }
 
+/// Globally unique identity of a game: the chain that created it and that chain's counter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameIdentity {
+    pub creator: ChainId,
+    pub number: u64,
+}
+
+impl GameIdentity {
+    /// Game ID used as the key on every chain, e.g. `<creator chain>-000001`
+    pub fn id(&self) -> String {
+        format!("{}-{:06}", self.creator, self.number)
+    }
+
+    /// Whether `game_id` was issued by `chain`
+    pub fn is_created_by(game_id: &str, chain: ChainId) -> bool {
+        game_id.strip_prefix(&chain.to_string()).is_some_and(|number| number.starts_with('-'))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, GraphQLMutationRoot)]
 pub enum Operation {
-    CreateGame { opponent_chain: ChainId, time_control: u32 },
+    CreateGame { opponent_chain: ChainId, time_control: Option<TimeControl>, preset: Option<TimeControlPreset>, starting_position: Option<String>, chess960: bool, variant: Variant, game_chain: Option<ChainId> },
     AcceptGame { game_id: String },
+    DeclineGame { game_id: String },
+    CancelGame { game_id: String },
     PlaceMove { game_id: String, from: String, to: String, promotion: Option<String> },
+    PlaceMoveNotation { game_id: String, notation: String, format: MoveNotation },
+    PlaceDrop { game_id: String, piece: String, square: String },
+    LinkBughouse { game_id: String, partner_game_id: String, partner_white: ChainId, partner_black: ChainId },
     ResignGame { game_id: String },
     OfferDraw { game_id: String },
     AcceptDraw { game_id: String },
+    DeclineDraw { game_id: String },
+    ClaimDraw { game_id: String },
     TimeoutGame { game_id: String },
     SetBettingChain { betting_chain: ChainId },
+    SetBettingApp { betting_app: ApplicationId },
     SetHubChain { hub_chain: ChainId },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Message {
-    GameInvite { game_id: String },
-    OpponentMove { game_id: String, chess_move: ChessMove },
-    DrawOffer { game_id: String },
+    GameInvite { identity: GameIdentity, time_control: TimeControl, fen: String, chess960: bool, variant: Variant, seed: Option<u64>, game_chain: Option<ChainId>, created_at: u64 },
+    HostGame { game: ChessGame, seed: Option<u64>, betting_chain: Option<ChainId>, hub_chain: Option<ChainId> },
+    HostedOperation { operation: Operation },
+    HostedOperationRejected { game_id: String, reason: GameError },
+    OpponentMove { game_id: String, chess_move: ChessMove, zobrist_key: u64, time_left: u64 },
+    GameDeclined { game_id: String },
+    GameCancelled { game_id: String, status: GameStatus },
+    OpenBetting { game_id: String, black_player: ChainId, game_chain: Option<ChainId> },
+    RefundBets { game_id: String },
+    RequestResync { game_id: String },
+    Resync { game: ChessGame, position_history: Vec<u64> },
+    DrawOffer { game_id: String, ply: u32 },
     DrawAccepted { game_id: String },
-    GameStarted { game_id: String },
+    DrawDeclined { game_id: String },
+    DrawClaimed { game_id: String, termination: Termination },
+    GameStarted { game_id: String, seed: Option<u64>, started_at: u64 },
     PositionUpdated { game_id: String, fen: String, move_count: u32 },
-    GameFinished { game_id: String, result: GameResult },
+    BughouseLinked { game_id: String, partner: PartnerBoard },
+    PocketPiece { game_id: String, piece: String },
+    GameFinished { game_id: String, result: GameResult, termination: Option<Termination> },
     RegisterGame { game_id: String, white_player: ChainId, black_player: ChainId },
     UpdateHubLeaderboard { winner: ChainId },
 }
@@ -45,12 +85,26 @@ pub enum Message {
 pub enum OperationResult {
     GameCreated { game_id: String },
     GameAccepted { game_id: String },
+    GameDeclined { game_id: String },
+    GameCancelled { game_id: String },
     MoveAccepted,
     GameResigned { game_id: String },
     DrawOffered,
     DrawAccepted,
+    DrawDeclined,
+    DrawClaimed,
     Timeout { game_id: String },
+    BughouseLinked { game_id: String },
     ConfigUpdated,
+    Forwarded { game_id: String },
+    CreateRejected { reason: GameError },
+    AcceptRejected { reason: GameError },
+    CancelRejected { reason: GameError },
+    MoveRejected { reason: GameError },
+    ResignRejected { reason: GameError },
+    DrawRejected { reason: GameError },
+    TimeoutRejected { reason: GameError },
+    LinkRejected { reason: GameError },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,5 +112,49 @@ pub enum GameEvent {
     GameCreated { game_id: String, white_player: String, black_player: String },
     GameStarted { game_id: String },
     MoveMade { game_id: String, chess_move: ChessMove, new_fen: String },
-    GameFinished { game_id: String, result: GameResult },
+    GameFinished { game_id: String, result: GameResult, termination: Option<Termination> },
+    GameCancelled { game_id: String, status: GameStatus },
+    GameUpdated { game: ChessGame },
+}
+
+impl OperationResult {
+    /// Why the operation was rejected, none when it succeeded
+    pub fn rejection(&self) -> Option<&GameError> {
+        match self {
+            OperationResult::CreateRejected { reason }
+            | OperationResult::AcceptRejected { reason }
+            | OperationResult::CancelRejected { reason }
+            | OperationResult::MoveRejected { reason }
+            | OperationResult::ResignRejected { reason }
+            | OperationResult::DrawRejected { reason }
+            | OperationResult::TimeoutRejected { reason }
+            | OperationResult::LinkRejected { reason } => Some(reason),
+            _ => None,
+        }
+    }
+}
+
+impl Operation {
+    /// Game an operation acts on, none for creating games and configuration
+    pub fn game_id(&self) -> Option<&str> {
+        match self {
+            Operation::CreateGame { .. }
+            | Operation::SetBettingChain { .. }
+            | Operation::SetBettingApp { .. }
+            | Operation::SetHubChain { .. } => None,
+            Operation::AcceptGame { game_id }
+            | Operation::DeclineGame { game_id }
+            | Operation::CancelGame { game_id }
+            | Operation::PlaceMove { game_id, .. }
+            | Operation::PlaceMoveNotation { game_id, .. }
+            | Operation::PlaceDrop { game_id, .. }
+            | Operation::LinkBughouse { game_id, .. }
+            | Operation::ResignGame { game_id }
+            | Operation::OfferDraw { game_id }
+            | Operation::AcceptDraw { game_id }
+            | Operation::DeclineDraw { game_id }
+            | Operation::ClaimDraw { game_id }
+            | Operation::TimeoutGame { game_id } => Some(game_id),
+        }
+    }
 }
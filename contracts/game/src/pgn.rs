@@ -0,0 +1,217 @@
+//! Portable Game Notation export
+//!
+//! Renders a `ChessGame` and its move history in PGN export format: the
+//! Seven Tag Roster followed by supplemental tags, then movetext wrapped at
+//! 80 columns with elapsed-time comments taken from the move timestamps and
+//! a closing comment naming how the game ended.
+
+use crate::chess_logic::{Color, Position};
+use abi::{ChessGame, ChessMove, ClockMode, GameResult, GameStatus, Termination, TimeControl, Variant};
+
+const MAX_LINE_LEN: usize = 79;
+const MICROS_PER_SECOND: u64 = 1_000_000;
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Render a game as PGN
+pub fn write_pgn(game: &ChessGame, moves: &[ChessMove]) -> String {
+    let result = result_tag(game.result);
+    let mut pgn = String::new();
+
+    // Seven Tag Roster, in the order the standard requires
+    push_tag(&mut pgn, "Event", "Speed Chess");
+    push_tag(&mut pgn, "Site", "Linera");
+    push_tag(&mut pgn, "Date", &date_tag(game.created_at));
+    push_tag(&mut pgn, "Round", "-");
+    push_tag(&mut pgn, "White", &game.white_player);
+    push_tag(&mut pgn, "Black", &game.black_player);
+    push_tag(&mut pgn, "Result", result);
+
+    // Supplemental tags
+    if let Some(variant) = variant_tag(game) {
+        push_tag(&mut pgn, "Variant", variant);
+    }
+    if game.chess960 || game.fen != Position::starting_fen(game.variant) {
+        push_tag(&mut pgn, "SetUp", "1");
+        push_tag(&mut pgn, "FEN", &game.fen);
+    }
+    push_tag(&mut pgn, "TimeControl", &time_control_tag(&game.time_control));
+    push_tag(&mut pgn, "Termination", termination_tag(game.status, game.termination));
+    pgn.push('\n');
+
+    let mut tokens = movetext(game, moves);
+    if let Some(termination) = game.termination {
+        tokens.push(format!("{{{}}}", termination_reason(termination)));
+    }
+    tokens.push(result.to_string());
+    pgn.push_str(&wrap(&tokens));
+    pgn.push('\n');
+    pgn
+}
+
+/// Move numbers, SAN and clock comments as separate tokens
+fn movetext(game: &ChessGame, moves: &[ChessMove]) -> Vec<String> {
+    let (mut number, mut color) = Position::from_fen_variant(&game.fen, game.variant)
+        .map(|position| (position.fullmove_number, position.active_color))
+        .unwrap_or((1, Color::White));
+
+    let mut tokens = Vec::new();
+    let mut previous_timestamp = None;
+    for (index, chess_move) in moves.iter().enumerate() {
+        if color == Color::White {
+            tokens.push(format!("{}.", number));
+        } else if index == 0 {
+            tokens.push(format!("{}...", number));
+        }
+
+        if chess_move.san.is_empty() {
+            tokens.push(format!("{}{}", chess_move.from, chess_move.to));
+        } else {
+            tokens.push(chess_move.san.clone());
+        }
+
+        // The first move has no earlier timestamp to measure from
+        if let Some(previous) = previous_timestamp {
+            let elapsed = chess_move.timestamp.saturating_sub(previous) / MICROS_PER_SECOND;
+            tokens.push(format!("{{[%emt {}]}}", clock(elapsed)));
+        }
+        previous_timestamp = Some(chess_move.timestamp);
+
+        if color == Color::Black {
+            number += 1;
+        }
+        color = color.opposite();
+    }
+    tokens
+}
+
+/// Join tokens with spaces, breaking lines before they exceed the PGN limit
+fn wrap(tokens: &[String]) -> String {
+    let mut text = String::new();
+    let mut line_len = 0;
+    for token in tokens {
+        if line_len > 0 && line_len + 1 + token.len() > MAX_LINE_LEN {
+            text.push('\n');
+            line_len = 0;
+        } else if line_len > 0 {
+            text.push(' ');
+            line_len += 1;
+        }
+        text.push_str(token);
+        line_len += token.len();
+    }
+    text
+}
+
+fn push_tag(pgn: &mut String, name: &str, value: &str) {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    pgn.push_str(&format!("[{} \"{}\"]\n", name, escaped));
+}
+
+fn result_tag(result: Option<GameResult>) -> &'static str {
+    match result {
+        Some(GameResult::WhiteWins) => "1-0",
+        Some(GameResult::BlackWins) => "0-1",
+        Some(GameResult::Draw) => "1/2-1/2",
+        Some(GameResult::InProgress) | None => "*",
+    }
+}
+
+/// Variant name as lichess writes it, none for standard chess
+fn variant_tag(game: &ChessGame) -> Option<&'static str> {
+    match game.variant {
+        Variant::Standard if game.chess960 => Some("Chess960"),
+        Variant::Standard => None,
+        Variant::KingOfTheHill => Some("King of the Hill"),
+        Variant::ThreeCheck => Some("Three-check"),
+        Variant::Antichess => Some("Antichess"),
+        Variant::Crazyhouse => Some("Crazyhouse"),
+        Variant::Bughouse => Some("Bughouse"),
+    }
+}
+
+/// Standard PGN termination value; the exact reason goes in a movetext comment
+fn termination_tag(status: GameStatus, termination: Option<Termination>) -> &'static str {
+    match (status, termination) {
+        (GameStatus::Finished, Some(Termination::Timeout | Termination::TimeoutVsInsufficientMaterial)) => "time forfeit",
+        (GameStatus::Finished, Some(_)) => "normal",
+        (GameStatus::Aborted, _) => "abandoned",
+        _ => "unterminated",
+    }
+}
+
+/// Human-readable reason, written as a comment before the game result
+fn termination_reason(termination: Termination) -> &'static str {
+    match termination {
+        Termination::Checkmate => "Checkmate",
+        Termination::Stalemate => "Stalemate",
+        Termination::InsufficientMaterial => "Insufficient material",
+        Termination::ThreefoldRepetition => "Threefold repetition",
+        Termination::FivefoldRepetition => "Fivefold repetition",
+        Termination::FiftyMoveRule => "Fifty-move rule",
+        Termination::SeventyFiveMoveRule => "Seventy-five-move rule",
+        Termination::Resignation => "Resignation",
+        Termination::Agreement => "Draw by agreement",
+        Termination::KingInCenter => "King reached the center",
+        Termination::ThirdCheck => "Third check",
+        Termination::AllPiecesLost => "All pieces lost",
+        Termination::Timeout => "Time forfeit",
+        Termination::TimeoutVsInsufficientMaterial => "Time forfeit against insufficient material",
+    }
+}
+
+/// Stages as `moves/seconds+increment` joined by `:`, or `-` for untimed games
+///
+/// PGN has no notation for delays, so a simple delay is written with `d` and
+/// a Bronstein delay with `b` in place of the `+`.
+fn time_control_tag(control: &TimeControl) -> String {
+    if control.stages.is_empty() {
+        return "-".to_string();
+    }
+    let separator = match control.mode {
+        ClockMode::Increment => '+',
+        ClockMode::SimpleDelay => 'd',
+        ClockMode::BronsteinDelay => 'b',
+    };
+    let fields: Vec<String> = control
+        .stages
+        .iter()
+        .map(|stage| {
+            let mut field = String::new();
+            if stage.moves > 0 {
+                field.push_str(&format!("{}/", stage.moves));
+            }
+            field.push_str(&stage.seconds.to_string());
+            if stage.bonus > 0 {
+                field.push_str(&format!("{}{}", separator, stage.bonus));
+            }
+            field
+        })
+        .collect();
+    fields.join(":")
+}
+
+/// `h:mm:ss` as used by `%clk` and `%emt` comments
+fn clock(seconds: u64) -> String {
+    format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+/// `YYYY.MM.DD` for a UTC timestamp in microseconds
+fn date_tag(micros: u64) -> String {
+    let (year, month, day) = civil_date(micros / MICROS_PER_SECOND / SECONDS_PER_DAY);
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+/// Gregorian date for a count of days since 1970-01-01
+fn civil_date(days: u64) -> (u64, u64, u64) {
+    // Shift the epoch to 0000-03-01 so leap days fall at the end of each year
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    (year, month, day)
+}
@@ -0,0 +1,25 @@
+//! Resignation
+//!
+//! Either player may resign an active game at any point, including on the
+//! opponent's turn. The opponent wins at once.
+
+use abi::{ChessGame, GameError, GameResult, GameStatus, Termination};
+
+/// End the game as a loss for `player`
+pub fn resign(game: &mut ChessGame, player: &str) -> Result<(), GameError> {
+    if game.status != GameStatus::Active {
+        return Err(GameError::GameNotActive);
+    }
+    let result = if player == game.white_player {
+        GameResult::BlackWins
+    } else if player == game.black_player {
+        GameResult::WhiteWins
+    } else {
+        return Err(GameError::NotAPlayer);
+    };
+    game.status = GameStatus::Finished;
+    game.result = Some(result);
+    game.termination = Some(Termination::Resignation);
+    game.draw_offer = None;
+    Ok(())
+}
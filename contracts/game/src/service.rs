@@ -1,11 +1,16 @@
 #![cfg_attr(target_arch = "wasm32", no_main)]
 
+mod bitboard;
 mod chess_logic;
+mod clock;
+mod invite;
+mod pgn;
 mod state;
 
+use self::chess_logic::{Color, Position};
 use self::state::GameState;
-use async_graphql::{Context, EmptySubscription, Object, Schema};
-use abi::{ChessGame, ChessMove};
+use async_graphql::{Context, EmptySubscription, Object, Schema, SimpleObject};
+use abi::{ChessGame, ChessMove, GameStatus, TimeControl};
 use game::{GameAbi, Operation};
 use linera_sdk::graphql::GraphQLMutationRoot;
 use linera_sdk::linera_base_types::WithServiceAbi;
@@ -39,7 +44,7 @@ impl Service for GameService {
             .expect("Failed to load state");
 
         let schema = Schema::build(
-            QueryRoot,
+            QueryRoot { now: self.runtime.system_time().micros() },
             Operation::mutation_root(self.runtime.clone()),
             EmptySubscription
         )
@@ -49,8 +54,32 @@ impl Service for GameService {
     }
 }
 
+/// Current position of a game
+#[derive(SimpleObject)]
+pub struct GamePosition {
+    pub fen: String,
+    /// Zobrist key as 16 hex digits
+    pub zobrist_key: String,
+}
+
+/// Both clocks of a game as of the query
+#[derive(SimpleObject)]
+pub struct GameClock {
+    /// White's remaining time in microseconds
+    pub white_time_left: u64,
+    /// Black's remaining time in microseconds
+    pub black_time_left: u64,
+    /// Stages and per-move bonus the clocks run under
+    pub time_control: TimeControl,
+    /// Whether the side to move's clock is running
+    pub running: bool,
+}
+
 /// GraphQL query root
-pub struct QueryRoot;
+pub struct QueryRoot {
+    /// Time the query runs at, for live clocks
+    now: u64,
+}
 
 #[Object]
 impl QueryRoot {
@@ -67,11 +96,25 @@ impl QueryRoot {
         state.get_active_games().await
     }
 
-    /// Get all games (including pending)
+    /// Get all games (including pending), leaving out called-off games and expired invites
     #[graphql(name = "allGames")]
     async fn all_games(&self, ctx: &Context<'_>) -> Result<Vec<ChessGame>, String> {
         let state = ctx.data::<GameState>().map_err(|e| format!("{:?}", e))?;
-        state.get_all_games().await
+        let mut games = state.get_all_games().await?;
+        games.retain(|game| !invite::is_cancelled(game) && !invite::expired(game, self.now));
+        Ok(games)
+    }
+
+    /// Why the game chain rejected our last forwarded operation on a hosted game, if it did
+    #[graphql(name = "hostedRejection")]
+    async fn hosted_rejection(&self, ctx: &Context<'_>, #[graphql(name = "gameId")] game_id: String) -> Result<Option<String>, String> {
+        let state = ctx.data::<GameState>().map_err(|e| format!("{:?}", e))?;
+        let reason = state
+            .hosted_rejections
+            .get(&game_id)
+            .await
+            .map_err(|e| format!("Failed to get rejection: {}", e))?;
+        Ok(reason.map(|reason| reason.to_string()))
     }
 
     /// Get move history for a game
@@ -90,15 +133,67 @@ impl QueryRoot {
             .ok_or_else(|| "No move history found".to_string())
     }
 
-    /// Get current FEN position for a game
-    async fn position(&self, ctx: &Context<'_>, #[graphql(name = "gameId")] game_id: String) -> Result<String, String> {
+    /// Get current FEN position and its Zobrist key for a game
+    async fn position(&self, ctx: &Context<'_>, #[graphql(name = "gameId")] game_id: String) -> Result<GamePosition, String> {
         let state = ctx.data::<GameState>().map_err(|e| format!("{:?}", e))?;
-        state
+        let fen = state
             .position_fen
             .get(&game_id)
             .await
             .map_err(|e| format!("Failed to get position: {}", e))?
-            .ok_or_else(|| "No position found".to_string())
+            .ok_or_else(|| "No position found".to_string())?;
+        let variant = state.get_game(&game_id).await.map(|game| game.variant).unwrap_or_default();
+        let zobrist_key = format!(
+            "{:016x}",
+            Position::from_fen_variant(&fen, variant).map_err(|e| e.to_string())?.zobrist_key()
+        );
+
+        Ok(GamePosition { fen, zobrist_key })
+    }
+
+    /// Remaining time on both clocks, counting down the side to move
+    async fn clock(&self, ctx: &Context<'_>, #[graphql(name = "gameId")] game_id: String) -> Result<GameClock, String> {
+        let state = ctx.data::<GameState>().map_err(|e| format!("{:?}", e))?;
+        let game = state
+            .get_game(&game_id)
+            .await
+            .ok_or_else(|| format!("Game {} not found", game_id))?;
+        let to_move = Position::from_fen_variant(&game.current_fen, game.variant)
+            .map_err(|e| e.to_string())?
+            .active_color;
+        let running = clock::is_timed(&game) && game.status == GameStatus::Active;
+        let (white_time_left, black_time_left) = if running {
+            (
+                clock::time_left(&game, Color::White, to_move, self.now),
+                clock::time_left(&game, Color::Black, to_move, self.now),
+            )
+        } else {
+            (game.white_time_left, game.black_time_left)
+        };
+
+        Ok(GameClock {
+            white_time_left,
+            black_time_left,
+            time_control: game.time_control,
+            running,
+        })
+    }
+
+    /// Export a game as PGN, finished or still in progress
+    async fn pgn(&self, ctx: &Context<'_>, #[graphql(name = "gameId")] game_id: String) -> Result<String, String> {
+        let state = ctx.data::<GameState>().map_err(|e| format!("{:?}", e))?;
+        let game = state
+            .get_game(&game_id)
+            .await
+            .ok_or_else(|| format!("Game {} not found", game_id))?;
+        let history = state
+            .move_history
+            .get(&game_id)
+            .await
+            .map_err(|e| format!("Failed to get move history: {}", e))?
+            .unwrap_or_else(|| game.moves.clone());
+
+        Ok(pgn::write_pgn(&game, &history))
     }
 
     /// Get the last move for a game
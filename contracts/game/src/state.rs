@@ -1,5 +1,6 @@
-use abi::{ChessGame, ChessMove, GameStatus};
-use linera_sdk::linera_base_types::ChainId;
+use abi::{ChessGame, ChessMove, GameError, GameStatus};
+use game::GameIdentity;
+use linera_sdk::linera_base_types::{ApplicationId, ChainId};
 use linera_sdk::views::{MapView, RegisterView, RootView, ViewStorageContext};
 
 /// Application state for the Game contract
@@ -9,7 +10,7 @@ pub struct GameState {
     /// All games indexed by game ID
     pub games: MapView<String, ChessGame>,
 
-    /// Counter for generating unique game IDs
+    /// Counter for the games this chain creates, combined with the chain ID into game IDs
     pub next_game_id: RegisterView<u64>,
 
     /// Active games lookup (for quick filtering)
@@ -21,22 +22,40 @@ pub struct GameState {
     /// Complete move history for each game
     pub move_history: MapView<String, Vec<ChessMove>>,
 
+    /// Zobrist key after every ply for each game (repetition detection)
+    pub position_history: MapView<String, Vec<u64>>,
+
+    /// Creator's share of the Chess960 seed for games awaiting acceptance
+    pub chess960_seeds: MapView<String, u64>,
+
+    /// Bughouse partner pieces credited before their PocketPiece message arrived
+    pub pocket_advances: MapView<String, String>,
+
+    /// Why the game chain rejected our last forwarded operation on each hosted game
+    pub hosted_rejections: MapView<String, GameError>,
+
     /// Current FEN position for each game (for quick access)
     pub position_fen: MapView<String, String>,
 
     /// Reference to the Betting Chain (for odds updates)
     pub betting_chain: RegisterView<Option<ChainId>>,
 
+    /// Betting application, called on the betting chain to refund cancelled games
+    pub betting_app: RegisterView<Option<ApplicationId>>,
+
+    /// Chains that may call off each game's bets, recorded on the betting chain when the game is created
+    pub refund_chains: MapView<String, Vec<ChainId>>,
+
     /// Reference to the Hub Chain (for leaderboard updates)
     pub hub_chain: RegisterView<Option<ChainId>>,
 }
 
 impl GameState {
-    /// Generate a new unique game ID
-    pub async fn generate_game_id(&mut self) -> String {
-        let id = *self.next_game_id.get();
-        self.next_game_id.set(id + 1);
-        format!("game_{:06}", id)
+    /// Generate the identity of a new game created by `creator`, unique across chains
+    pub async fn generate_game_id(&mut self, creator: ChainId) -> GameIdentity {
+        let number = *self.next_game_id.get();
+        self.next_game_id.set(number + 1);
+        GameIdentity { creator, number }
     }
 
     /// Get a game by ID
@@ -77,6 +96,126 @@ impl GameState {
         Ok(())
     }
 
+    /// Get the position keys reached so far in a game
+    pub async fn get_position_history(&self, game_id: &str) -> Result<Vec<u64>, String> {
+        Ok(self
+            .position_history
+            .get(game_id)
+            .await
+            .map_err(|e| format!("Failed to get position history: {}", e))?
+            .unwrap_or_default())
+    }
+
+    /// Record a position key and return the full history including it
+    pub async fn add_position_key(&mut self, game_id: &str, key: u64) -> Result<Vec<u64>, String> {
+        let mut history = self.get_position_history(game_id).await?;
+        history.push(key);
+
+        self.position_history
+            .insert(game_id, history.clone())
+            .map_err(|e| format!("Failed to update position history: {}", e))?;
+
+        Ok(history)
+    }
+
+    /// Replace the key of the current position, the last one recorded
+    pub async fn replace_position_key(&mut self, game_id: &str, key: u64) -> Result<(), String> {
+        let mut history = self.get_position_history(game_id).await?;
+        match history.last_mut() {
+            Some(last) => *last = key,
+            None => history.push(key),
+        }
+
+        self.position_history
+            .insert(game_id, history)
+            .map_err(|e| format!("Failed to update position history: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Restart the position history from a single key
+    pub async fn reset_position_history(&mut self, game_id: &str, key: u64) -> Result<(), String> {
+        self.position_history
+            .insert(game_id, vec![key])
+            .map_err(|e| format!("Failed to reset position history: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Remove and return the creator's Chess960 seed share for a game
+    pub async fn take_chess960_seed(&mut self, game_id: &str) -> Result<Option<u64>, String> {
+        let seed = self
+            .chess960_seeds
+            .get(game_id)
+            .await
+            .map_err(|e| format!("Failed to get Chess960 seed: {}", e))?;
+        self.chess960_seeds
+            .remove(game_id)
+            .map_err(|e| format!("Failed to remove Chess960 seed: {}", e))?;
+
+        Ok(seed)
+    }
+
+    /// Remember a partner piece credited ahead of its PocketPiece message
+    pub async fn add_pocket_advance(&mut self, game_id: &str, letter: &str) -> Result<(), String> {
+        let mut advances = self
+            .pocket_advances
+            .get(game_id)
+            .await
+            .map_err(|e| format!("Failed to get pocket advances: {}", e))?
+            .unwrap_or_default();
+        advances.push_str(letter);
+
+        self.pocket_advances
+            .insert(game_id, advances)
+            .map_err(|e| format!("Failed to update pocket advances: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Settle a PocketPiece message against an earlier credit, returning whether one was found
+    pub async fn take_pocket_advance(&mut self, game_id: &str, letter: &str) -> Result<bool, String> {
+        let mut advances = self
+            .pocket_advances
+            .get(game_id)
+            .await
+            .map_err(|e| format!("Failed to get pocket advances: {}", e))?
+            .unwrap_or_default();
+        let Some(index) = advances.find(letter) else {
+            return Ok(false);
+        };
+        advances.remove(index);
+
+        self.pocket_advances
+            .insert(game_id, advances)
+            .map_err(|e| format!("Failed to update pocket advances: {}", e))?;
+
+        Ok(true)
+    }
+
+    /// Overwrite a game with a copy resynced from another chain
+    pub async fn restore_game(&mut self, mut game: ChessGame, position_history: Vec<u64>) -> Result<(), String> {
+        let game_id = game.id.clone();
+        game.desynced = false;
+        self.move_history
+            .insert(&game_id, game.moves.clone())
+            .map_err(|e| format!("Failed to restore move history: {}", e))?;
+        self.position_history
+            .insert(&game_id, position_history)
+            .map_err(|e| format!("Failed to restore position history: {}", e))?;
+        self.update_position(&game_id, game.current_fen.clone()).await?;
+        self.update_game(game).await
+    }
+
+    /// Store the latest copy of a game hosted on another chain
+    pub async fn mirror_game(&mut self, game: ChessGame) -> Result<(), String> {
+        self.move_history
+            .insert(&game.id, game.moves.clone())
+            .map_err(|e| format!("Failed to mirror move history: {}", e))?;
+        self.update_position(&game.id, game.current_fen.clone()).await?;
+        self.update_game(game).await
+    }
+
     /// Update the current FEN position
     pub async fn update_position(&mut self, game_id: &str, fen: String) -> Result<(), String> {
         self.position_fen
@@ -0,0 +1,68 @@
+//! Keeping both players' copies of a game in step
+//!
+//! Each player replays the opponent's moves on its own copy. An arriving move
+//! must come from the side to move and reach the position key the sender
+//! reached; otherwise our copy has drifted and is replaced by the reference
+//! copy, held on the game chain of a hosted game and else on the creator's
+//! chain.
+
+use crate::chess_logic::{Color, Position};
+use abi::ChessGame;
+
+/// Chain holding the reference copy of `game`
+pub fn authoritative(game: &ChessGame) -> &str {
+    game.game_chain.as_deref().unwrap_or(&game.white_player)
+}
+
+/// Check a move from `origin` may be replayed on `position`, our copy before it
+pub fn check_turn(game: &ChessGame, position: &Position, origin: &str) -> Result<(), String> {
+    let to_move = match position.active_color {
+        Color::White => &game.white_player,
+        Color::Black => &game.black_player,
+    };
+    if origin != to_move {
+        return Err("Move out of turn".to_string());
+    }
+    Ok(())
+}
+
+/// Check the replayed `position` matches the key the sender reached
+pub fn check_key(position: &Position, received: u64) -> Result<(), String> {
+    if position.sync_key() != received {
+        return Err(format!("Position key {:016x} differs from the sender's {:016x}", position.sync_key(), received));
+    }
+    Ok(())
+}
+
+/// Store `position` as the current position of our copy, with its key
+pub fn set_position(game: &mut ChessGame, position: &Position) {
+    game.current_fen = position.to_fen();
+    game.zobrist_key = format!("{:016x}", position.zobrist_key());
+}
+
+/// Flag our copy as drifted on `chain`
+///
+/// Returns whether `chain` must ask for the reference copy; the
+/// authoritative chain keeps its copy as it is and pushes it instead.
+pub fn mark_desynced(game: &mut ChessGame, chain: &str) -> bool {
+    if authoritative(game) == chain {
+        return false;
+    }
+    game.desynced = true;
+    true
+}
+
+/// Whether this chain, holding the reference copy `game`, answers a resync request from `origin`
+pub fn answers_request(game: &ChessGame, chain: &str, origin: &str) -> bool {
+    authoritative(game) == chain && (origin == game.white_player || origin == game.black_player)
+}
+
+/// Whether a reference copy from `origin` may replace our copy `local`
+///
+/// Only the authoritative chain of our copy may send one, and only for the
+/// same pair of players.
+pub fn accepts_resync(local: &ChessGame, incoming: &ChessGame, origin: &str) -> bool {
+    origin == authoritative(local)
+        && incoming.white_player == local.white_player
+        && incoming.black_player == local.black_player
+}
@@ -27,7 +27,7 @@ export function ChessBoard({ gameId }: ChessBoardProps) {
       <div className="mb-4">
         <div className="flex items-center justify-between mb-3">
           <h2 className="text-xl font-semibold text-white">
-            Game {gameId.slice(5, 11)}
+            Game {gameId.slice(-6)}
           </h2>
           <span className={`px-3 py-1 rounded-full text-sm font-semibold ${
             currentGame?.status === 'Active' ? 'bg-green-500/20 text-green-400' :
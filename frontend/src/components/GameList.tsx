@@ -55,7 +55,7 @@ export function GameList({ onSelectGame, selectedGameId }: GameListProps) {
               }`}
             >
               <div className="flex items-center justify-between mb-2">
-                <span className="font-semibold text-white text-sm">Game {game.id.slice(5, 11)}</span>
+                <span className="font-semibold text-white text-sm">Game {game.id.slice(-6)}</span>
                 <span className="text-xs bg-green-500/20 text-green-400 px-2 py-1 rounded">
                   {game.status}
                 </span>
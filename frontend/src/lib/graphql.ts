@@ -111,7 +111,12 @@ export const MAKE_MOVE = `
 
 export const CREATE_GAME = `
   mutation CreateGame($opponentChain: String!, $timeControl: Int!) {
-    createGame(opponentChain: $opponentChain, timeControl: $timeControl)
+    createGame(
+      opponentChain: $opponentChain
+      timeControl: { mode: INCREMENT, stages: [{ moves: 0, seconds: $timeControl, bonus: 0 }] }
+      chess960: false
+      variant: STANDARD
+    )
   }
 `;
 
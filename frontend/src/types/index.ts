@@ -2,7 +2,8 @@ export enum GameStatus {
   Pending = 'Pending',
   Active = 'Active',
   Finished = 'Finished',
-  Abandoned = 'Abandoned',
+  Cancelled = 'Cancelled',
+  Aborted = 'Aborted',
 }
 
 export enum GameResult {